@@ -0,0 +1,123 @@
+//! Host-Angaben parsen/auflösen: Hostnamen, IPv4, IPv6 (auch `[..]:port` und `%zone`).
+
+use std::io::{Error, ErrorKind};
+use std::net::{IpAddr, Ipv6Addr, SocketAddr, SocketAddrV6, ToSocketAddrs};
+
+/// Zerlegt `host`, `host:port`, `::1`, `[::1]` oder `[::1]:port`.
+/// Ohne Port-Angabe gilt `default_port`. Klammern werden entfernt.
+pub fn split_host_port(s: &str, default_port: u16) -> Result<(String, u16), String> {
+    let s = s.trim();
+    if let Some(rest) = s.strip_prefix('[') {
+        let (host, tail) = rest
+            .split_once(']')
+            .ok_or_else(|| format!("fehlende ']' in '{s}'"))?;
+        let port = match tail {
+            "" => default_port,
+            t => t
+                .strip_prefix(':')
+                .and_then(|p| p.parse().ok())
+                .ok_or_else(|| format!("Ungültiger Port in '{s}'"))?,
+        };
+        return Ok((host.to_string(), port));
+    }
+    match s.split_once(':') {
+        // mehr als ein ':' → nackte IPv6-Adresse ohne Port
+        Some((_, rest)) if rest.contains(':') => Ok((s.to_string(), default_port)),
+        Some((host, port)) => {
+            let port = port
+                .parse()
+                .map_err(|_| format!("Ungültiger Port in '{s}'"))?;
+            Ok((host.to_string(), port))
+        }
+        None => Ok((s.to_string(), default_port)),
+    }
+}
+
+/// Host ohne `[..]` – so, wie ihn Resolver und `direct-tcpip` erwarten.
+pub(crate) fn bare_host(host: &str) -> &str {
+    host.trim()
+        .strip_prefix('[')
+        .and_then(|h| h.strip_suffix(']'))
+        .unwrap_or(host.trim())
+}
+
+/// Löst `host` auf; IPv6 oder IPv4 zuerst, sonst Reihenfolge des Resolvers.
+pub(crate) fn resolve(
+    host: &str,
+    port: u16,
+    prefer_ipv6: bool,
+) -> std::io::Result<Vec<SocketAddr>> {
+    let host = bare_host(host);
+
+    // Link-local mit Zone (`fe80::1%eth0`) kann der std-Resolver nicht
+    let mut addrs: Vec<SocketAddr> = match host.split_once('%') {
+        Some((ip, zone)) => {
+            let ip: Ipv6Addr = ip.parse().map_err(|_| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("'{host}' ist keine IPv6-Adresse"),
+                )
+            })?;
+            vec![SocketAddr::V6(SocketAddrV6::new(
+                ip,
+                port,
+                0,
+                scope_id(zone)?,
+            ))]
+        }
+        None => match host.parse::<IpAddr>() {
+            Ok(ip) => vec![SocketAddr::new(ip, port)],
+            Err(_) => (host, port).to_socket_addrs()?.collect(),
+        },
+    };
+    // stabil sortieren → innerhalb einer Familie bleibt die Resolver-Reihenfolge
+    addrs.sort_by_key(|a| a.is_ipv6() != prefer_ipv6);
+    Ok(addrs)
+}
+
+/// Zone-ID: numerisch oder (Unix) Interface-Name.
+fn scope_id(zone: &str) -> std::io::Result<u32> {
+    if let Ok(id) = zone.parse() {
+        return Ok(id);
+    }
+    #[cfg(unix)]
+    {
+        let name =
+            std::ffi::CString::new(zone).map_err(|_| Error::from(ErrorKind::InvalidInput))?;
+        // SAFETY: gültiger, nullterminierter String; die Funktion liest ihn nur
+        let idx = unsafe { libc::if_nametoindex(name.as_ptr()) };
+        if idx != 0 {
+            return Ok(idx);
+        }
+    }
+    Err(Error::new(
+        ErrorKind::NotFound,
+        format!("Interface '{zone}' unbekannt"),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_host_port_forms() {
+        let ok = |h: &str, p: u16| Ok((h.to_string(), p));
+        assert_eq!(split_host_port("::1", 22), ok("::1", 22));
+        assert_eq!(split_host_port("[::1]:22", 2222), ok("::1", 22));
+        assert_eq!(split_host_port("[::1]", 2222), ok("::1", 2222));
+        assert_eq!(split_host_port("example.com", 22), ok("example.com", 22));
+        assert_eq!(
+            split_host_port("example.com:2200", 22),
+            ok("example.com", 2200)
+        );
+        assert_eq!(split_host_port("10.0.0.1:22", 0), ok("10.0.0.1", 22));
+    }
+
+    #[test]
+    fn split_host_port_errors() {
+        assert!(split_host_port("[::1", 22).is_err());
+        assert!(split_host_port("[::1]x", 22).is_err());
+        assert!(split_host_port("host:port", 22).is_err());
+    }
+}
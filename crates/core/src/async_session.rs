@@ -0,0 +1,187 @@
+//! Async-Fassade (Feature `async`) für Dienste auf tokio. Die Session selbst bleibt
+//! die blockierende `StarrSession`; alles, was warten kann (Verbinden, Schreiben bei
+//! vollem Window, `read_until`, Schließen), läuft im Blocking-Pool von tokio.
+//! Lesen pollt den Puffer des Reader-Threads mit dessen `poll_interval`.
+
+use crate::{ExecOutput, StarrError, StarrProfile, StarrSession};
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio::time::Sleep;
+
+/// Blockierende Arbeit im Blocking-Pool; ein Panic dort wird zu `Protocol`.
+async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, StarrError> + Send + 'static,
+) -> Result<T, StarrError> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| StarrError::Protocol(e.into()))?
+}
+
+/// Gemeinsames Handle von Session und Readern. Wer zuletzt loslässt, räumt im
+/// Blocking-Pool auf – `shutdown` wartet bis zu drei Sekunden auf den Server.
+struct Shared(Arc<StarrSession>);
+
+impl Drop for Shared {
+    fn drop(&mut self) {
+        if Arc::strong_count(&self.0) != 1 {
+            return;
+        }
+        if let Ok(rt) = tokio::runtime::Handle::try_current() {
+            let sess = self.0.clone();
+            rt.spawn_blocking(move || drop(sess));
+        }
+    }
+}
+
+/// `StarrSession` mit `async fn`s. Die synchrone API bleibt über `as_sync` erreichbar.
+pub struct AsyncStarrSession {
+    inner: Shared,
+}
+
+impl From<StarrSession> for AsyncStarrSession {
+    /// Übernimmt eine synchron aufgebaute Session (z. B. mit `connect_with_prompt`).
+    fn from(sess: StarrSession) -> Self {
+        Self {
+            inner: Shared(Arc::new(sess)),
+        }
+    }
+}
+
+impl AsyncStarrSession {
+    /// Öffnet SSH, PTY und Shell wie `StarrSession::connect`.
+    pub async fn connect(p: &StarrProfile) -> Result<Self, StarrError> {
+        let p = p.clone();
+        blocking(move || StarrSession::connect(&p))
+            .await
+            .map(Self::from)
+    }
+
+    /// Einzelnes Kommando ohne PTY wie `StarrSession::exec`.
+    pub async fn exec(p: &StarrProfile, command: &str) -> Result<ExecOutput, StarrError> {
+        let (p, command) = (p.clone(), command.to_string());
+        blocking(move || StarrSession::exec(&p, &command)).await
+    }
+
+    /// Die blockierende Session, für alles ohne async-Gegenstück (Forwarding, SFTP, …).
+    pub fn as_sync(&self) -> &StarrSession {
+        &self.inner.0
+    }
+
+    /// Sendet `data` unverändert (kein \n anhängen). Reihenfolge wie bei `send`,
+    /// solange Aufrufe nacheinander awaited werden.
+    pub async fn send(&self, data: &str) -> Result<(), StarrError> {
+        self.send_bytes(data.as_bytes()).await
+    }
+
+    /// Wie `send`, aber als Bytes.
+    pub async fn send_bytes(&self, data: &[u8]) -> Result<(), StarrError> {
+        let (sess, data) = (self.inner.0.clone(), data.to_vec());
+        blocking(move || sess.send_bytes(&data)).await
+    }
+
+    /// Wartet auf Output und liefert alles bisher Gepufferte; leer heißt EOF.
+    pub async fn read(&self) -> Result<Vec<u8>, StarrError> {
+        let sess = &self.inner.0;
+        loop {
+            // Erst prüfen, dann lesen – sonst ginge der letzte Rest vor dem Schließen verloren
+            let closed = sess.is_closed();
+            let bytes = sess.read_bytes();
+            if !bytes.is_empty() || closed {
+                return Ok(bytes);
+            }
+            tokio::time::sleep(sess.reader_cfg.poll_interval).await;
+        }
+    }
+
+    /// Wie `StarrSession::read_until`.
+    pub async fn read_until(&self, pattern: &str, timeout: Duration) -> Result<String, StarrError> {
+        let (sess, pattern) = (self.inner.0.clone(), pattern.to_string());
+        blocking(move || sess.read_until(&pattern, timeout)).await
+    }
+
+    /// PTY-Größe ändern, wie `StarrSession::resize`.
+    pub async fn resize(&self, cols: u32, rows: u32) -> Result<(), StarrError> {
+        let sess = self.inner.0.clone();
+        blocking(move || Ok(sess.resize(cols, rows)?)).await
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.inner.0.is_closed()
+    }
+
+    pub fn exit_status(&self) -> Option<i32> {
+        self.inner.0.exit_status()
+    }
+
+    /// Output als `AsyncRead` (z. B. für `tokio::io::copy`), bis der Remote schließt.
+    /// Teilt sich den Puffer mit `read`/`read_until` – mehrere Leser bekommen je einen Teil.
+    pub fn reader(&self) -> AsyncStarrReader {
+        AsyncStarrReader {
+            sess: Shared(self.inner.0.clone()),
+            pending: Vec::new(),
+            pos: 0,
+            sleep: None,
+        }
+    }
+
+    /// Schließt wie `StarrSession::close`. Lebt noch ein `reader`, bleibt die Session
+    /// bis zu dessen Drop offen.
+    pub async fn close(self) -> Result<(), StarrError> {
+        let sess = self.inner.0.clone();
+        drop(self);
+        blocking(move || match Arc::try_unwrap(sess) {
+            Ok(sess) => Ok(sess.close()?),
+            Err(_) => Ok(()),
+        })
+        .await
+    }
+}
+
+/// Output einer `AsyncStarrSession` als Byte-Strom; EOF, sobald der Remote schließt.
+pub struct AsyncStarrReader {
+    sess: Shared,
+    /// Schon aus dem Puffer geholt, aber noch nicht ausgeliefert
+    pending: Vec<u8>,
+    pos: usize,
+    /// Pause bis zum nächsten Blick in den Puffer
+    sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl AsyncRead for AsyncStarrReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            if this.pos < this.pending.len() {
+                let n = buf.remaining().min(this.pending.len() - this.pos);
+                buf.put_slice(&this.pending[this.pos..this.pos + n]);
+                this.pos += n;
+                return Poll::Ready(Ok(()));
+            }
+            let sess = &this.sess.0;
+            let closed = sess.is_closed();
+            this.pending = sess.read_bytes();
+            this.pos = 0;
+            if !this.pending.is_empty() {
+                continue;
+            }
+            if closed {
+                return Poll::Ready(Ok(()));
+            }
+            let interval = sess.reader_cfg.poll_interval;
+            let sleep = this
+                .sleep
+                .get_or_insert_with(|| Box::pin(tokio::time::sleep(interval)));
+            ready!(sleep.as_mut().poll(cx));
+            this.sleep = None;
+        }
+    }
+}
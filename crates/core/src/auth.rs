@@ -0,0 +1,178 @@
+//! Auth-Methoden jenseits von Key-Datei: Agent und keyboard-interactive.
+
+use crate::{emit, ConnectError, SessionEvent, StarrProfile};
+use anyhow::{anyhow, Result};
+use std::sync::mpsc::Sender;
+
+/// Ein einzelner keyboard-interactive-Prompt.
+#[derive(Debug, Clone)]
+pub struct AuthPrompt {
+    pub text: String,
+    /// false → Eingabe maskieren (Passwort, OTP)
+    pub echo: bool,
+}
+
+/// Frontends beantworten keyboard-interactive-Prompts (z. B. 2FA/OTP).
+pub trait PromptResponder {
+    /// Genau eine Antwort pro Prompt, in derselben Reihenfolge.
+    fn respond(
+        &mut self,
+        username: &str,
+        instructions: &str,
+        prompts: &[AuthPrompt],
+    ) -> Vec<String>;
+}
+
+/// Fallback ohne Frontend: beantwortet verdeckte Prompts mit dem Passwort.
+struct PasswordResponder<'a>(&'a str);
+
+impl PromptResponder for PasswordResponder<'_> {
+    fn respond(
+        &mut self,
+        _username: &str,
+        _instructions: &str,
+        prompts: &[AuthPrompt],
+    ) -> Vec<String> {
+        prompts
+            .iter()
+            .map(|p| {
+                if p.echo {
+                    String::new()
+                } else {
+                    self.0.to_string()
+                }
+            })
+            .collect()
+    }
+}
+
+/// Abgelaufenes Passwort: erklärt dem Nutzer, warum plötzlich nach einem neuen gefragt wird.
+struct ExpiredResponder<'r>(&'r mut dyn PromptResponder);
+
+impl PromptResponder for ExpiredResponder<'_> {
+    fn respond(
+        &mut self,
+        username: &str,
+        instructions: &str,
+        prompts: &[AuthPrompt],
+    ) -> Vec<String> {
+        let note = "Das Passwort ist abgelaufen und muss geändert werden.";
+        let instructions = if instructions.trim().is_empty() {
+            note.to_string()
+        } else {
+            format!("{note}\n{instructions}")
+        };
+        self.0.respond(username, &instructions, prompts)
+    }
+}
+
+/// LIBSSH2_ERROR_PASSWORD_EXPIRED: Server antwortet mit PASSWD_CHANGEREQ.
+const LIBSSH2_ERROR_PASSWORD_EXPIRED: i32 = -15;
+
+/// Brücke zu ssh2s Prompt-Trait.
+struct KbdInt<'r>(&'r mut dyn PromptResponder);
+
+impl ssh2::KeyboardInteractivePrompt for KbdInt<'_> {
+    fn prompt<'a>(
+        &mut self,
+        username: &str,
+        instructions: &str,
+        prompts: &[ssh2::Prompt<'a>],
+    ) -> Vec<String> {
+        let prompts: Vec<AuthPrompt> = prompts
+            .iter()
+            .map(|p| AuthPrompt {
+                text: p.text.to_string(),
+                echo: p.echo,
+            })
+            .collect();
+        self.0.respond(username, instructions, &prompts)
+    }
+}
+
+/// Passwort-Auth; bietet der Server nur keyboard-interactive an, geht es darüber.
+///
+/// Verlangt der Server eine Passwortänderung (PASSWD_CHANGEREQ), gibt es dafür in ssh2
+/// keinen Callback. Stattdessen geht es über keyboard-interactive weiter – dort fragt
+/// PAM nach altem und neuem Passwort. Ohne Prompt oder ohne keyboard-interactive:
+/// `ConnectError::PasswordExpired`.
+pub(crate) fn password_or_kbdint(
+    sess: &ssh2::Session,
+    p: &StarrProfile,
+    prompt: Option<&mut dyn PromptResponder>,
+    events: Option<&Sender<SessionEvent>>,
+) -> Result<()> {
+    let methods = sess.auth_methods(&p.user).unwrap_or("password").to_string();
+    let offers = |m: &str| methods.split(',').any(|x| x == m);
+
+    if let Some(ref pw) = p.password {
+        if offers("password") {
+            emit(events, SessionEvent::Authenticating("password"));
+            match sess.userauth_password(&p.user, pw) {
+                Ok(()) => return Ok(()),
+                Err(e)
+                    if matches!(
+                        e.code(),
+                        ssh2::ErrorCode::Session(LIBSSH2_ERROR_PASSWORD_EXPIRED)
+                    ) =>
+                {
+                    emit(events, SessionEvent::PasswordExpired);
+                    let Some(r) = prompt.filter(|_| offers("keyboard-interactive")) else {
+                        return Err(ConnectError::PasswordExpired {
+                            user: p.user.clone(),
+                        }
+                        .into());
+                    };
+                    emit(events, SessionEvent::Authenticating("keyboard-interactive"));
+                    sess.userauth_keyboard_interactive(
+                        &p.user,
+                        &mut KbdInt(&mut ExpiredResponder(r)),
+                    )?;
+                    return Ok(());
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    if offers("keyboard-interactive") {
+        emit(events, SessionEvent::Authenticating("keyboard-interactive"));
+        match (prompt, p.password.as_deref()) {
+            (Some(r), _) => sess.userauth_keyboard_interactive(&p.user, &mut KbdInt(r))?,
+            (None, Some(pw)) => sess
+                .userauth_keyboard_interactive(&p.user, &mut KbdInt(&mut PasswordResponder(pw)))?,
+            (None, None) => {
+                return Err(anyhow!(
+                    "keyboard-interactive verlangt, aber kein Prompt verfügbar"
+                ))
+            }
+        }
+        return Ok(());
+    }
+
+    Err(anyhow!(
+        "Server bietet weder password noch keyboard-interactive an ({methods})"
+    ))
+}
+
+/// Probiert alle Identitäten aus ssh-agent / Pageant der Reihe nach.
+pub(crate) fn agent(sess: &ssh2::Session, user: &str) -> Result<()> {
+    let mut agent = sess.agent()?;
+    agent
+        .connect()
+        .map_err(|e| anyhow!("SSH-Agent nicht erreichbar: {e}"))?;
+    agent.list_identities()?;
+    let ids = agent.identities()?;
+
+    for id in &ids {
+        if agent.userauth(user, id).is_ok() && sess.authenticated() {
+            let _ = agent.disconnect();
+            return Ok(());
+        }
+    }
+    let _ = agent.disconnect();
+    Err(anyhow!(
+        "Agent-Auth fehlgeschlagen ({} Identitäten probiert)",
+        ids.len()
+    ))
+}
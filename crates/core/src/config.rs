@@ -0,0 +1,457 @@
+//! Gespeicherte Profile in `config.toml` + Secrets im OS-Schlüsselbund
+//! (Windows Credential Manager, macOS Keychain, Secret Service).
+
+use crate::{config_dir, StarrProfile};
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::path::PathBuf;
+
+/// Service-Name der Einträge im Schlüsselbund.
+const KEYRING_SERVICE: &str = "starr";
+
+/// Schriftgröße des Terminals, solange nichts anderes eingestellt ist.
+pub const DEFAULT_FONT_SIZE: f32 = 15.0;
+pub const DEFAULT_SCROLLBACK_LINES: usize = 2000;
+
+/// Inhalt von `config.toml`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StarrConfig {
+    #[serde(default)]
+    pub ui: UiSettings,
+    #[serde(default)]
+    pub behavior: BehaviorSettings,
+    #[serde(default)]
+    pub security: SecuritySettings,
+    #[serde(default)]
+    pub profiles: Vec<SavedProfile>,
+    /// Formular der letzten Verbindung (ohne Secrets), beim Start wieder eingesetzt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_form: Option<SavedProfile>,
+}
+
+/// Darstellung des Terminals (`[ui]` in `config.toml`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiSettings {
+    pub font_size: f32,
+    /// Eigene Monospace-Schrift (TTF/OTF); `None` = eingebaute egui-Schrift
+    pub font_path: Option<PathBuf>,
+    pub theme: Theme,
+    /// Terminalfarben (`[ui.palette]`)
+    pub palette: Palette,
+    /// Fenster beim letzten Beenden (`[ui.window]`); `None` → Standardgröße
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub window: Option<WindowGeometry>,
+}
+
+impl Default for UiSettings {
+    fn default() -> Self {
+        Self {
+            font_size: DEFAULT_FONT_SIZE,
+            font_path: None,
+            theme: Theme::Dark,
+            palette: Palette::default(),
+            window: None,
+        }
+    }
+}
+
+/// Größe (Innenmaß) und Position (Außenkante) des Hauptfensters in logischen Pixeln.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub size: [f32; 2],
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pos: Option<[f32; 2]>,
+    #[serde(default)]
+    pub maximized: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+    /// Hell/Dunkel vom Betriebssystem übernehmen
+    System,
+}
+
+/// Farbe als `"#rrggbb"` in der Config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgb(pub [u8; 3]);
+
+impl Rgb {
+    const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self([r, g, b])
+    }
+}
+
+impl Serialize for Rgb {
+    fn serialize<S: Serializer>(&self, s: S) -> std::result::Result<S::Ok, S::Error> {
+        let [r, g, b] = self.0;
+        s.serialize_str(&format!("#{r:02x}{g:02x}{b:02x}"))
+    }
+}
+
+impl<'de> Deserialize<'de> for Rgb {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(d)?;
+        let hex = s.strip_prefix('#').unwrap_or(&s);
+        let v = (hex.len() == 6)
+            .then(|| u32::from_str_radix(hex, 16).ok())
+            .flatten()
+            .ok_or_else(|| {
+                serde::de::Error::custom(format!("ungültige Farbe '{s}' (erwartet #rrggbb)"))
+            })?;
+        Ok(Self::new((v >> 16) as u8, (v >> 8) as u8, v as u8))
+    }
+}
+
+/// Vorder-/Hintergrund und die 16 ANSI-Grundfarben (0–7 normal, 8–15 hell).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Palette {
+    pub foreground: Rgb,
+    pub background: Rgb,
+    pub ansi: [Rgb; 16],
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::starr()
+    }
+}
+
+/// Name und Konstruktor einer mitgelieferten Palette.
+pub type PalettePreset = (&'static str, fn() -> Palette);
+
+impl Palette {
+    /// Mitgelieferte Vorlagen für die Einstellungen.
+    pub const PRESETS: [PalettePreset; 4] = [
+        ("Starr", Palette::starr),
+        ("Campbell", Palette::campbell),
+        ("Solarized Dark", Palette::solarized_dark),
+        ("Solarized Light", Palette::solarized_light),
+    ];
+
+    /// Bisherige Standardfarben
+    pub fn starr() -> Self {
+        Self {
+            foreground: Rgb::new(230, 230, 230),
+            background: Rgb::new(10, 10, 14),
+            ansi: [
+                Rgb::new(0, 0, 0),
+                Rgb::new(205, 49, 49),
+                Rgb::new(13, 188, 121),
+                Rgb::new(229, 229, 16),
+                Rgb::new(36, 114, 200),
+                Rgb::new(188, 63, 188),
+                Rgb::new(17, 168, 205),
+                Rgb::new(229, 229, 229),
+                Rgb::new(102, 102, 102),
+                Rgb::new(241, 76, 76),
+                Rgb::new(35, 209, 139),
+                Rgb::new(245, 245, 67),
+                Rgb::new(59, 142, 234),
+                Rgb::new(214, 112, 214),
+                Rgb::new(41, 184, 219),
+                Rgb::new(255, 255, 255),
+            ],
+        }
+    }
+
+    /// Windows Terminal / Konsole
+    pub fn campbell() -> Self {
+        Self {
+            foreground: Rgb::new(204, 204, 204),
+            background: Rgb::new(12, 12, 12),
+            ansi: [
+                Rgb::new(12, 12, 12),
+                Rgb::new(197, 15, 31),
+                Rgb::new(19, 161, 14),
+                Rgb::new(193, 156, 0),
+                Rgb::new(0, 55, 218),
+                Rgb::new(136, 23, 152),
+                Rgb::new(58, 150, 221),
+                Rgb::new(204, 204, 204),
+                Rgb::new(118, 118, 118),
+                Rgb::new(231, 72, 86),
+                Rgb::new(22, 198, 12),
+                Rgb::new(249, 241, 165),
+                Rgb::new(59, 120, 255),
+                Rgb::new(180, 0, 158),
+                Rgb::new(97, 214, 214),
+                Rgb::new(242, 242, 242),
+            ],
+        }
+    }
+
+    pub fn solarized_dark() -> Self {
+        Self {
+            foreground: Rgb::new(131, 148, 150),
+            background: Rgb::new(0, 43, 54),
+            ansi: SOLARIZED,
+        }
+    }
+
+    pub fn solarized_light() -> Self {
+        Self {
+            foreground: Rgb::new(101, 123, 131),
+            background: Rgb::new(253, 246, 227),
+            ansi: SOLARIZED,
+        }
+    }
+}
+
+/// Solarized-Belegung der 16 Farben (hell/dunkel teilen sich die Tabelle).
+const SOLARIZED: [Rgb; 16] = [
+    Rgb::new(7, 54, 66),
+    Rgb::new(220, 50, 47),
+    Rgb::new(133, 153, 0),
+    Rgb::new(181, 137, 0),
+    Rgb::new(38, 139, 210),
+    Rgb::new(211, 54, 130),
+    Rgb::new(42, 161, 152),
+    Rgb::new(238, 232, 213),
+    Rgb::new(0, 43, 54),
+    Rgb::new(203, 75, 22),
+    Rgb::new(88, 110, 117),
+    Rgb::new(101, 123, 131),
+    Rgb::new(131, 148, 150),
+    Rgb::new(108, 113, 196),
+    Rgb::new(147, 161, 161),
+    Rgb::new(253, 246, 227),
+];
+
+/// Verhalten der Sessions (`[behavior]`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BehaviorSettings {
+    /// Getipptes sofort lokal anzeigen, bis der Server selbst echot
+    /// (normale PTY-Shells tun das – daher aus per Default)
+    pub local_echo: bool,
+    /// Alt+Taste als `ESC` + Taste senden (Meta für Emacs/readline)
+    pub alt_sends_escape: bool,
+    /// Rechtsklick fügt direkt ein statt das Kontextmenü zu öffnen (PuTTY)
+    pub right_click_paste: bool,
+    /// Markieren kopiert sofort in die Zwischenablage (PuTTY); aus → nur per Menü/Ctrl+Shift+C
+    pub copy_on_select: bool,
+    /// Vor dem Senden mehrzeiliger Zwischenablage nachfragen
+    pub warn_multiline_paste: bool,
+    pub bell: BellMode,
+    /// Zeilen Scrollback oberhalb des sichtbaren Bereichs
+    pub scrollback_lines: usize,
+    /// Neue Tabs folgen der Ausgabe
+    pub autoscroll: bool,
+    /// Neue Tabs brechen lange Zeilen am Fensterrand um; aus → breites Terminal, horizontal scrollen
+    pub wrap_lines: bool,
+    /// Vorgabe für „Bei Verbindungsabbruch neu verbinden“
+    pub auto_reconnect: bool,
+    /// Eingabezeile unter dem Terminal (Zeile tippen, Enter sendet) statt nur direkt tippen
+    pub input_bar: bool,
+    /// Verlauf der Eingabezeile im gespeicherten Profil merken
+    pub persist_input_history: bool,
+    /// Session nach so vielen Minuten ohne Ausgabe schließen (0 = nie). Tote Verbindungen
+    /// erkennt schon das Keepalive – das hier ist nur für Sitzungen, die man vergisst.
+    pub idle_timeout_mins: u32,
+}
+
+impl Default for BehaviorSettings {
+    fn default() -> Self {
+        Self {
+            local_echo: false,
+            alt_sends_escape: true,
+            right_click_paste: false,
+            copy_on_select: true,
+            warn_multiline_paste: true,
+            bell: BellMode::Flash,
+            scrollback_lines: DEFAULT_SCROLLBACK_LINES,
+            autoscroll: true,
+            wrap_lines: true,
+            auto_reconnect: true,
+            input_bar: false,
+            persist_input_history: false,
+            idle_timeout_mins: 0,
+        }
+    }
+}
+
+/// Reaktion auf BEL (`\x07`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BellMode {
+    /// Terminal kurz aufblitzen lassen
+    #[default]
+    Flash,
+    /// System-Signalton
+    Sound,
+    Off,
+}
+
+/// Host-Key-Prüfung, Agent und was der Remote lokal darf (`[security]`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SecuritySettings {
+    /// Eigene known_hosts statt `~/.ssh/known_hosts`
+    pub known_hosts_path: Option<PathBuf>,
+    /// Vorgabe für „SSH-Agent / Pageant verwenden“
+    pub use_agent: bool,
+    /// Remote darf per OSC 52 die lokale Zwischenablage setzen (vim/tmux-Yank); aus per Default
+    pub remote_clipboard: bool,
+}
+
+/// Profil unter einem Namen. Passwort/Passphrase landen nie in der Datei,
+/// nur `has_secret` – das Secret selbst liegt im Schlüsselbund.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedProfile {
+    pub name: String,
+    #[serde(default)]
+    pub has_secret: bool,
+    pub profile: StarrProfile,
+    /// Verlauf der Eingabezeile, älteste zuerst (nur mit `persist_input_history`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub input_history: Vec<String>,
+    /// Farbe für Tab und Banner, damit z. B. Produktion sofort auffällt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<Rgb>,
+    /// Kurze Kennung im Tab und Banner („PROD“)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Jeden Befehl aus der Eingabezeile vor dem Senden bestätigen lassen
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub confirm_send: bool,
+}
+
+impl SavedProfile {
+    pub fn new(name: impl Into<String>, mut profile: StarrProfile) -> Self {
+        strip_secrets(&mut profile);
+        Self {
+            name: name.into(),
+            has_secret: false,
+            profile,
+            input_history: Vec::new(),
+            color: None,
+            label: None,
+            confirm_send: false,
+        }
+    }
+
+    /// Profil zum Verbinden; holt das Secret aus dem Schlüsselbund, falls vorhanden.
+    /// Klappt das nicht, fehlt es eben → Auth fragt interaktiv nach.
+    pub fn to_profile(&self) -> StarrProfile {
+        let mut p = self.profile.clone();
+        self.fill_secret(&mut p);
+        p
+    }
+
+    /// Setzt das gespeicherte Secret in `p`, sofern dort weder Passwort noch Passphrase stehen.
+    pub fn fill_secret(&self, p: &mut StarrProfile) {
+        if !self.has_secret || p.password.is_some() || p.key_passphrase.is_some() {
+            return;
+        }
+        if let Ok(Some(secret)) = load_secret(&self.name) {
+            apply_secret(p, &secret);
+        }
+    }
+}
+
+impl StarrConfig {
+    /// Lädt `config.toml`; fehlt die Datei, gibt es eine leere Konfig.
+    pub fn load() -> Result<Self> {
+        let path = config_path()?;
+        let text = match std::fs::read_to_string(&path) {
+            Ok(t) => t,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e).with_context(|| format!("{} nicht lesbar", path.display())),
+        };
+        toml::from_str(&text).with_context(|| format!("{} ungültig", path.display()))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let mut cfg = self.clone();
+        for sp in cfg.profiles.iter_mut().chain(&mut cfg.last_form) {
+            strip_secrets(&mut sp.profile);
+        }
+        let text = toml::to_string_pretty(&cfg).context("Konfig nicht serialisierbar")?;
+        let path = config_path()?;
+        std::fs::write(&path, text).with_context(|| format!("{} nicht schreibbar", path.display()))
+    }
+
+    pub fn find(&self, name: &str) -> Option<&SavedProfile> {
+        self.profiles.iter().find(|p| p.name == name)
+    }
+
+    pub fn find_mut(&mut self, name: &str) -> Option<&mut SavedProfile> {
+        self.profiles.iter_mut().find(|p| p.name == name)
+    }
+
+    /// Ersetzt ein gleichnamiges Profil oder hängt es an.
+    pub fn upsert(&mut self, profile: SavedProfile) {
+        match self.profiles.iter_mut().find(|p| p.name == profile.name) {
+            Some(existing) => *existing = profile,
+            None => self.profiles.push(profile),
+        }
+    }
+
+    /// Entfernt das Profil samt Secret.
+    pub fn remove(&mut self, name: &str) {
+        if let Some(i) = self.profiles.iter().position(|p| p.name == name) {
+            if self.profiles.remove(i).has_secret {
+                let _ = delete_secret(name);
+            }
+        }
+    }
+}
+
+/// `%APPDATA%\Starr\config.toml` (bzw. Pendant auf Linux/macOS).
+pub fn config_path() -> Result<PathBuf> {
+    Ok(config_dir()?.join("config.toml"))
+}
+
+/// Speichert Passwort/Passphrase eines Profils im Schlüsselbund.
+pub fn save_secret(profile_name: &str, secret: &str) -> Result<()> {
+    entry(profile_name)?
+        .set_password(secret)
+        .map_err(|e| anyhow!("Schlüsselbund: {e}"))
+}
+
+/// Secret eines Profils; `None`, wenn keins gespeichert ist.
+pub fn load_secret(profile_name: &str) -> Result<Option<String>> {
+    match entry(profile_name)?.get_password() {
+        Ok(s) => Ok(Some(s)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(anyhow!("Schlüsselbund: {e}")),
+    }
+}
+
+pub fn delete_secret(profile_name: &str) -> Result<()> {
+    match entry(profile_name)?.delete_password() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(anyhow!("Schlüsselbund: {e}")),
+    }
+}
+
+fn entry(profile_name: &str) -> Result<keyring::Entry> {
+    keyring::Entry::new(KEYRING_SERVICE, profile_name).map_err(|e| anyhow!("Schlüsselbund: {e}"))
+}
+
+/// Passwort/Passphrase (auch der Jump-Hops) entfernen.
+fn strip_secrets(p: &mut StarrProfile) {
+    p.password = None;
+    p.key_passphrase = None;
+    if let Some(j) = p.jump.as_deref_mut() {
+        strip_secrets(j);
+    }
+}
+
+/// Mit Key → Passphrase, sonst Passwort; Jump-Hops erben wie bei `set_jump_hosts`.
+fn apply_secret(p: &mut StarrProfile, secret: &str) {
+    if p.key_path.is_some() || !p.identities.is_empty() {
+        p.key_passphrase = Some(secret.to_string());
+    } else {
+        p.password = Some(secret.to_string());
+    }
+    if let Some(j) = p.jump.as_deref_mut() {
+        apply_secret(j, secret);
+    }
+}
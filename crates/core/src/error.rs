@@ -0,0 +1,192 @@
+//! Fehlerarten an der öffentlichen API. Intern bleibt es bei `anyhow`; an den
+//! Grenzen (`connect`, `send`, `exec`) wird einmal eingeordnet.
+
+use crate::ConnectError;
+use std::time::Duration;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+// libssh2-Fehlercodes, nach denen eingeordnet wird
+const LIBSSH2_ERROR_TIMEOUT: i32 = -9;
+const LIBSSH2_ERROR_SOCKET_DISCONNECT: i32 = -13;
+const LIBSSH2_ERROR_PASSWORD_EXPIRED: i32 = -15;
+const LIBSSH2_ERROR_AUTHENTICATION_FAILED: i32 = -18;
+const LIBSSH2_ERROR_PUBLICKEY_UNVERIFIED: i32 = -19;
+const LIBSSH2_ERROR_CHANNEL_CLOSED: i32 = -26;
+const LIBSSH2_ERROR_CHANNEL_EOF_SENT: i32 = -27;
+const LIBSSH2_ERROR_SOCKET_RECV: i32 = -43;
+
+/// Wofür sich ein Frontend entscheiden kann: Neu verbinden (`Io`, `Timeout`, `Closed`),
+/// neu anmelden (`Auth`), Host-Key bestätigen (`HostKey`) oder aufgeben (`Protocol`).
+/// Die Anzeige ist die der ursprünglichen Fehlermeldung.
+#[derive(Debug, thiserror::Error)]
+pub enum StarrError {
+    /// Netzwerk/Dateisystem, inkl. DNS
+    #[error(transparent)]
+    Io(std::io::Error),
+    /// Anmeldung gescheitert (falsches Passwort, Key abgelehnt, Passwort abgelaufen, …).
+    /// `allowed`: Methoden, die der Server danach noch anbietet (leer, falls unbekannt).
+    #[error("{source}{}", accepts_hint(allowed))]
+    Auth {
+        source: BoxError,
+        allowed: Vec<String>,
+    },
+    /// Host-Key unbekannt oder geändert; `UnknownHostKey` geht mit `trust_host_key` weiter
+    #[error(transparent)]
+    HostKey(ConnectError),
+    /// Connect, Handshake oder Socket hat nicht rechtzeitig geantwortet
+    #[error(transparent)]
+    Timeout(BoxError),
+    /// Alles andere, was das SSH-Protokoll bzw. libssh2 meldet
+    #[error(transparent)]
+    Protocol(BoxError),
+    /// Channel/Verbindung ist bereits zu
+    #[error("Verbindung geschlossen")]
+    Closed,
+}
+
+/// `read_until` hat das Muster nicht rechtzeitig gesehen (steckt in `StarrError::Timeout`).
+/// `partial` ist alles, was bis dahin gelesen wurde.
+#[derive(Debug, thiserror::Error)]
+#[error("'{pattern}' nicht innerhalb von {timeout:?} gesehen")]
+pub struct ReadTimeout {
+    pub pattern: String,
+    pub timeout: Duration,
+    pub partial: String,
+}
+
+/// „ (Server akzeptiert: publickey, keyboard-interactive)“
+fn accepts_hint(allowed: &[String]) -> String {
+    if allowed.is_empty() {
+        String::new()
+    } else {
+        format!(" (Server akzeptiert: {})", allowed.join(", "))
+    }
+}
+
+impl StarrError {
+    pub(crate) fn auth(source: impl Into<BoxError>) -> Self {
+        Self::Auth {
+            source: source.into(),
+            allowed: Vec::new(),
+        }
+    }
+
+    /// Lohnt ein neuer Verbindungsversuch mit denselben Zugangsdaten?
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::Io(_) | Self::Timeout(_) | Self::Closed)
+    }
+}
+
+impl From<std::io::Error> for StarrError {
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::TimedOut => Self::Timeout(e.into()),
+            _ => Self::Io(e),
+        }
+    }
+}
+
+/// Einordnung eines libssh2-Fehlers; `e` ist der Fehler selbst oder eine Hülle darum.
+fn from_ssh2_code(code: ssh2::ErrorCode, e: impl Into<BoxError>) -> StarrError {
+    match code {
+        ssh2::ErrorCode::Session(LIBSSH2_ERROR_TIMEOUT) => StarrError::Timeout(e.into()),
+        ssh2::ErrorCode::Session(
+            LIBSSH2_ERROR_PASSWORD_EXPIRED
+            | LIBSSH2_ERROR_AUTHENTICATION_FAILED
+            | LIBSSH2_ERROR_PUBLICKEY_UNVERIFIED,
+        ) => StarrError::auth(e),
+        ssh2::ErrorCode::Session(
+            LIBSSH2_ERROR_SOCKET_DISCONNECT
+            | LIBSSH2_ERROR_CHANNEL_CLOSED
+            | LIBSSH2_ERROR_CHANNEL_EOF_SENT
+            | LIBSSH2_ERROR_SOCKET_RECV,
+        ) => StarrError::Closed,
+        _ => StarrError::Protocol(e.into()),
+    }
+}
+
+impl From<ssh2::Error> for StarrError {
+    fn from(e: ssh2::Error) -> Self {
+        from_ssh2_code(e.code(), e)
+    }
+}
+
+impl From<ConnectError> for StarrError {
+    fn from(e: ConnectError) -> Self {
+        match e {
+            ConnectError::UnknownHostKey { .. } | ConnectError::HostKeyChanged { .. } => {
+                Self::HostKey(e)
+            }
+            ConnectError::ConnectTimeout { .. } | ConnectError::HandshakeTimeout { .. } => {
+                Self::Timeout(e.into())
+            }
+            ConnectError::PasswordExpired { .. } => Self::auth(e),
+            // Ganz einpacken: `source()` führt weiter zum DNS-Fehler
+            ConnectError::Resolve { ref source, .. } => {
+                Self::Io(std::io::Error::new(source.kind(), e))
+            }
+        }
+    }
+}
+
+/// Einordnung an der API-Grenze: bereits eingeordnet → unverändert, sonst nach
+/// der eigentlichen Ursache (ConnectError, io, libssh2), Rest → `Protocol`.
+impl From<anyhow::Error> for StarrError {
+    fn from(e: anyhow::Error) -> Self {
+        let e = match e.downcast::<StarrError>() {
+            Ok(se) => return se,
+            Err(e) => e,
+        };
+        let e = match e.downcast::<ConnectError>() {
+            Ok(ce) => return ce.into(),
+            Err(e) => e,
+        };
+        if let Some(io) = e.downcast_ref::<std::io::Error>() {
+            return match io.kind() {
+                std::io::ErrorKind::TimedOut => Self::Timeout(e.into()),
+                _ => Self::Io(std::io::Error::new(io.kind(), e)),
+            };
+        }
+        if let Some(code) = e.downcast_ref::<ssh2::Error>().map(ssh2::Error::code) {
+            return from_ssh2_code(code, e);
+        }
+        Self::Protocol(e.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+    use std::io::ErrorKind;
+
+    /// Display jeder Ebene, von außen nach innen.
+    fn chain(e: &dyn std::error::Error) -> Vec<String> {
+        std::iter::successors(Some(e), |e| e.source())
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn resolve_error_keeps_source() {
+        let e: StarrError = ConnectError::Resolve {
+            host: "nirgendwo".into(),
+            source: std::io::Error::new(ErrorKind::NotFound, "Name unbekannt"),
+        }
+        .into();
+        assert!(matches!(&e, StarrError::Io(io) if io.kind() == ErrorKind::NotFound));
+        assert_eq!(chain(&e).last().map(String::as_str), Some("Name unbekannt"));
+    }
+
+    #[test]
+    fn anyhow_io_error_keeps_chain() {
+        let inner = std::io::Error::new(ErrorKind::ConnectionRefused, "abgelehnt");
+        let e: StarrError = Err::<(), _>(inner)
+            .context("Verbinden mit host:22")
+            .unwrap_err()
+            .into();
+        assert!(matches!(&e, StarrError::Io(io) if io.kind() == ErrorKind::ConnectionRefused));
+        assert_eq!(chain(&e), ["Verbinden mit host:22", "abgelehnt"]);
+    }
+}
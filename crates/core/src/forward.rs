@@ -0,0 +1,374 @@
+//! Port-Forwarding (-L, -D, -R) über eine bestehende SSH-Session.
+//! Jede Verbindung bekommt einen eigenen Channel + Pump-Thread.
+
+use crate::{retry, write_all_retry, LIBSSH2_ERROR_EAGAIN};
+use anyhow::{anyhow, Result};
+use std::io::{ErrorKind, Read};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// `[bind_addr:]bind_port:host:host_port` wie bei `ssh -L`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForwardSpec {
+    pub bind_addr: String,
+    pub bind_port: u16,
+    pub host: String,
+    pub host_port: u16,
+}
+
+impl FromStr for ForwardSpec {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parts: Vec<&str> = s.split(':').collect();
+        let (bind_addr, rest) = match parts.as_slice() {
+            [port, host, hport] => ("127.0.0.1", [*port, *host, *hport]),
+            [addr, port, host, hport] => (*addr, [*port, *host, *hport]),
+            _ => {
+                return Err(anyhow!(
+                    "Ungültige Forward-Angabe '{s}' (erwartet [bind:]port:host:hostport)"
+                ))
+            }
+        };
+        let port = |p: &str| {
+            p.parse::<u16>()
+                .map_err(|_| anyhow!("Ungültiger Port '{p}' in '{s}'"))
+        };
+        Ok(Self {
+            bind_addr: bind_addr.to_string(),
+            bind_port: port(rest[0])?,
+            host: rest[1].to_string(),
+            host_port: port(rest[2])?,
+        })
+    }
+}
+
+/// Läuft, solange der Handle lebt. Drop stoppt Listener und alle Channels.
+pub struct ForwardHandle {
+    local_addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    join: Option<thread::JoinHandle<()>>,
+}
+
+impl ForwardHandle {
+    /// Tatsächlich gebundene lokale Adresse (nützlich bei Port 0).
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+impl Drop for ForwardHandle {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(h) = self.join.take() {
+            let _ = h.join();
+        }
+    }
+}
+
+/// Läuft, solange der Handle lebt. Drop stoppt den Accept-Thread, alle Channels
+/// und meldet den Listener beim Server ab.
+pub struct RemoteForwardHandle {
+    remote_port: u16,
+    stop: Arc<AtomicBool>,
+    join: Option<thread::JoinHandle<()>>,
+}
+
+impl RemoteForwardHandle {
+    /// Port, auf dem der Server tatsächlich lauscht (interessant bei Port 0).
+    pub fn remote_port(&self) -> u16 {
+        self.remote_port
+    }
+}
+
+impl Drop for RemoteForwardHandle {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(h) = self.join.take() {
+            let _ = h.join();
+        }
+    }
+}
+
+pub(crate) fn forward_local(
+    sess: Arc<Mutex<ssh2::Session>>,
+    bind_addr: &str,
+    bind_port: u16,
+    remote_host: &str,
+    remote_port: u16,
+) -> Result<ForwardHandle> {
+    let remote_host = remote_host.to_string();
+    serve(bind_addr, bind_port, move |tcp, peer, stop| {
+        match open_direct(&sess, &remote_host, remote_port, peer) {
+            Ok(ch) => pump(tcp, ch, stop),
+            // Remote lehnt ab (Host nicht erreichbar o. ä.) → nur diese Verbindung zu
+            Err(_) => {
+                let _ = tcp.shutdown(Shutdown::Both);
+            }
+        }
+    })
+}
+
+/// Dynamisches Forwarding (`-D`): minimaler SOCKS5-Server, nur CONNECT ohne Auth.
+pub(crate) fn forward_socks(
+    sess: Arc<Mutex<ssh2::Session>>,
+    bind_addr: &str,
+    bind_port: u16,
+) -> Result<ForwardHandle> {
+    serve(bind_addr, bind_port, move |mut tcp, peer, stop| {
+        let target = match socks5_handshake(&mut tcp) {
+            Ok(t) => t,
+            Err(_) => {
+                let _ = tcp.shutdown(Shutdown::Both);
+                return;
+            }
+        };
+        match open_direct(&sess, &target.0, target.1, peer) {
+            Ok(ch) => {
+                if socks5_reply(&mut tcp, SOCKS_OK).is_ok() {
+                    pump(tcp, ch, stop);
+                }
+            }
+            Err(_) => {
+                let _ = socks5_reply(&mut tcp, SOCKS_CONN_REFUSED);
+                let _ = tcp.shutdown(Shutdown::Both);
+            }
+        }
+    })
+}
+
+/// Remote-Forwarding (`-R`): `tcpip-forward` beim Server, jede angenommene Verbindung
+/// wird lokal mit `local_host:local_port` verbunden.
+pub(crate) fn forward_remote(
+    sess: Arc<Mutex<ssh2::Session>>,
+    remote_port: u16,
+    local_host: &str,
+    local_port: u16,
+) -> Result<RemoteForwardHandle> {
+    let (mut listener, bound_port) = {
+        let s = sess.lock().unwrap();
+        retry(|| s.channel_forward_listen(remote_port, None, None)).map_err(|e| {
+            anyhow!("Server lauscht nicht auf Port {remote_port} (belegt oder Forwarding abgeschaltet?): {e}")
+        })?
+    };
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop_accept = stop.clone();
+    let local_host = local_host.to_string();
+
+    let join = thread::spawn(move || {
+        let mut conns: Vec<thread::JoinHandle<()>> = Vec::new();
+        while !stop_accept.load(Ordering::SeqCst) {
+            match listener.accept() {
+                Ok(mut ch) => {
+                    let stop_conn = stop_accept.clone();
+                    let host = local_host.clone();
+                    conns.push(thread::spawn(move || {
+                        match TcpStream::connect((host.as_str(), local_port)) {
+                            Ok(tcp) => pump(tcp, ch, &stop_conn),
+                            // Lokales Ziel nicht erreichbar → nur dieser Channel zu
+                            Err(_) => {
+                                let _ = retry(|| ch.close());
+                            }
+                        }
+                    }));
+                }
+                Err(e) if matches!(e.code(), ssh2::ErrorCode::Session(LIBSSH2_ERROR_EAGAIN)) => {
+                    thread::sleep(Duration::from_millis(50));
+                }
+                Err(_) => break,
+            }
+            conns.retain(|h| !h.is_finished());
+        }
+        for h in conns {
+            let _ = h.join();
+        }
+        // Drop des Listeners schickt `cancel-tcpip-forward`
+        drop(listener);
+    });
+
+    Ok(RemoteForwardHandle {
+        remote_port: bound_port,
+        stop,
+        join: Some(join),
+    })
+}
+
+/// Lokaler Listener + ein Thread pro Verbindung, bis der Handle gedroppt wird.
+fn serve<F>(bind_addr: &str, bind_port: u16, handler: F) -> Result<ForwardHandle>
+where
+    F: Fn(TcpStream, SocketAddr, &AtomicBool) + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind((bind_addr, bind_port))
+        .map_err(|e| anyhow!("Kann {bind_addr}:{bind_port} nicht binden: {e}"))?;
+    listener.set_nonblocking(true)?;
+    let local_addr = listener.local_addr()?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop_accept = stop.clone();
+    let handler = Arc::new(handler);
+
+    let join = thread::spawn(move || {
+        let mut conns: Vec<thread::JoinHandle<()>> = Vec::new();
+        while !stop_accept.load(Ordering::SeqCst) {
+            match listener.accept() {
+                Ok((tcp, peer)) => {
+                    let stop_conn = stop_accept.clone();
+                    let handler = handler.clone();
+                    conns.push(thread::spawn(move || handler(tcp, peer, &stop_conn)));
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(50));
+                }
+                Err(_) => break,
+            }
+            conns.retain(|h| !h.is_finished());
+        }
+        for h in conns {
+            let _ = h.join();
+        }
+    });
+
+    Ok(ForwardHandle {
+        local_addr,
+        stop,
+        join: Some(join),
+    })
+}
+
+/// Öffnet einen `direct-tcpip`-Channel zum Ziel, Quelle = lokaler Peer.
+fn open_direct(
+    sess: &Mutex<ssh2::Session>,
+    host: &str,
+    port: u16,
+    peer: SocketAddr,
+) -> Result<ssh2::Channel, ssh2::Error> {
+    let s = sess.lock().unwrap();
+    let src = peer.ip().to_string();
+    retry(|| s.channel_direct_tcpip(host, port, Some((src.as_str(), peer.port()))))
+}
+
+const SOCKS_OK: u8 = 0x00;
+const SOCKS_CONN_REFUSED: u8 = 0x05;
+const SOCKS_CMD_UNSUPPORTED: u8 = 0x07;
+const SOCKS_ATYP_UNSUPPORTED: u8 = 0x08;
+
+/// SOCKS5-Begrüßung + CONNECT-Request lesen (RFC 1928). Liefert Ziel-Host/Port.
+fn socks5_handshake(tcp: &mut TcpStream) -> std::io::Result<(String, u16)> {
+    use std::io::{Error, Write};
+
+    // Windows vererbt non-blocking vom Listener → fürs Handshake blockierend mit Timeout
+    tcp.set_nonblocking(false)?;
+    tcp.set_read_timeout(Some(Duration::from_secs(10)))?;
+
+    let mut head = [0u8; 2];
+    tcp.read_exact(&mut head)?;
+    if head[0] != 5 {
+        return Err(Error::new(ErrorKind::InvalidData, "kein SOCKS5"));
+    }
+    let mut methods = vec![0u8; head[1] as usize];
+    tcp.read_exact(&mut methods)?;
+    if !methods.contains(&0x00) {
+        tcp.write_all(&[5, 0xFF])?; // keine akzeptable Methode
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "nur 'no auth' unterstützt",
+        ));
+    }
+    tcp.write_all(&[5, 0x00])?;
+
+    let mut req = [0u8; 4];
+    tcp.read_exact(&mut req)?;
+    if req[1] != 0x01 {
+        socks5_reply(tcp, SOCKS_CMD_UNSUPPORTED)?;
+        return Err(Error::new(
+            ErrorKind::Unsupported,
+            "nur CONNECT unterstützt",
+        ));
+    }
+    let host = match req[3] {
+        0x01 => {
+            let mut a = [0u8; 4];
+            tcp.read_exact(&mut a)?;
+            std::net::Ipv4Addr::from(a).to_string()
+        }
+        0x03 => {
+            let mut len = [0u8; 1];
+            tcp.read_exact(&mut len)?;
+            let mut name = vec![0u8; len[0] as usize];
+            tcp.read_exact(&mut name)?;
+            String::from_utf8_lossy(&name).into_owned()
+        }
+        0x04 => {
+            let mut a = [0u8; 16];
+            tcp.read_exact(&mut a)?;
+            std::net::Ipv6Addr::from(a).to_string()
+        }
+        _ => {
+            socks5_reply(tcp, SOCKS_ATYP_UNSUPPORTED)?;
+            return Err(Error::new(ErrorKind::Unsupported, "unbekannter Adresstyp"));
+        }
+    };
+    let mut port = [0u8; 2];
+    tcp.read_exact(&mut port)?;
+
+    tcp.set_read_timeout(None)?;
+    Ok((host, u16::from_be_bytes(port)))
+}
+
+/// Antwort auf den CONNECT-Request; BND.ADDR ist für Clients egal → 0.0.0.0:0.
+fn socks5_reply(tcp: &mut TcpStream, code: u8) -> std::io::Result<()> {
+    use std::io::Write;
+    tcp.write_all(&[5, code, 0, 0x01, 0, 0, 0, 0, 0, 0])
+}
+
+/// Schaufelt Bytes in beide Richtungen, bis eine Seite schließt oder `stop` gesetzt ist.
+pub(crate) fn pump(mut tcp: TcpStream, mut ch: ssh2::Channel, stop: &AtomicBool) {
+    let _ = tcp.set_nonblocking(true);
+    let mut buf = [0u8; 16 * 1024];
+    let mut local_eof = false;
+
+    while !stop.load(Ordering::SeqCst) {
+        let mut idle = true;
+
+        if !local_eof {
+            match tcp.read(&mut buf) {
+                Ok(0) => {
+                    local_eof = true;
+                    let _ = retry(|| ch.send_eof());
+                }
+                Ok(n) => {
+                    idle = false;
+                    if write_all_retry(&mut ch, &buf[..n]).is_err() {
+                        break;
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                Err(_) => break,
+            }
+        }
+
+        match ch.read(&mut buf) {
+            Ok(0) => break, // Remote-EOF
+            Ok(n) => {
+                idle = false;
+                if write_all_retry(&mut tcp, &buf[..n]).is_err() {
+                    break;
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+            Err(_) => break,
+        }
+
+        if idle {
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    let _ = ch.close();
+    let _ = tcp.shutdown(Shutdown::Both);
+}
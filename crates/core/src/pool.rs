@@ -0,0 +1,148 @@
+//! Warme Sessions für viele `exec`-Aufrufe an denselben Host: Handshake + Auth einmal,
+//! danach pro Kommando nur ein neuer Channel.
+
+use crate::{exec_on, open_session, ExecOutput, StarrError, StarrProfile};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// `(host, port, user)` – weitere Profilfelder (Jump, Algorithmen) unterscheiden nicht.
+type PoolKey = (String, u16, String);
+
+struct Entry {
+    session: PooledSession,
+    last_used: Instant,
+}
+
+/// Eine authentifizierte Session aus dem Pool. Klonen ist billig; parallele `exec`s
+/// auf derselben Session laufen nacheinander.
+#[derive(Clone)]
+pub struct PooledSession {
+    inner: Arc<Mutex<ssh2::Session>>,
+}
+
+impl PooledSession {
+    /// Kommando auf einem neuen Channel ausführen (ohne PTY).
+    pub fn exec(&self, command: &str) -> Result<ExecOutput, StarrError> {
+        let sess = self.inner.lock().unwrap();
+        sess.set_blocking(true);
+        let ch = sess.channel_session()?;
+        Ok(exec_on(&sess, ch, command)?)
+    }
+}
+
+/// Pool authentifizierter Sessions. Unbenutzte fliegen nach `ttl` raus
+/// (geprüft bei jedem Zugriff oder per `evict_idle`).
+pub struct SessionPool {
+    ttl: Duration,
+    sessions: Mutex<HashMap<PoolKey, Entry>>,
+}
+
+impl Default for SessionPool {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_TTL)
+    }
+}
+
+impl SessionPool {
+    pub const DEFAULT_TTL: Duration = Duration::from_secs(60);
+
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Vorhandene Session für `p` oder neu verbinden. Ohne Prompter: Auth muss über
+    /// Key, Agent oder Passwort im Profil gehen.
+    pub fn get_or_connect(&self, p: &StarrProfile) -> Result<PooledSession, StarrError> {
+        let key = (p.host.clone(), p.port, p.user.clone());
+        {
+            let mut sessions = self.sessions.lock().unwrap();
+            self.evict_locked(&mut sessions);
+            if let Some(e) = sessions.get_mut(&key) {
+                e.last_used = Instant::now();
+                return Ok(e.session.clone());
+            }
+        }
+        // Verbinden ohne Pool-Lock, andere Hosts warten nicht
+        let session = PooledSession {
+            inner: Arc::new(Mutex::new(open_session(p, None, None)?)),
+        };
+        let mut sessions = self.sessions.lock().unwrap();
+        let e = sessions.entry(key).or_insert(Entry {
+            session,
+            last_used: Instant::now(),
+        });
+        Ok(e.session.clone())
+    }
+
+    /// `exec` über eine gepoolte Session. Lässt sich auf der alten Session kein Channel
+    /// mehr öffnen (Verbindung serverseitig weg), wird einmal neu verbunden.
+    pub fn exec(&self, p: &StarrProfile, command: &str) -> Result<ExecOutput, StarrError> {
+        let session = self.get_or_connect(p)?;
+        let ch = {
+            let sess = session.inner.lock().unwrap();
+            sess.set_blocking(true);
+            sess.channel_session()
+        };
+        match ch {
+            Ok(ch) => Ok(exec_on(&session.inner.lock().unwrap(), ch, command)?),
+            Err(_) => {
+                self.remove(p);
+                self.get_or_connect(p)?.exec(command)
+            }
+        }
+    }
+
+    /// Session für `p` schließen und aus dem Pool nehmen.
+    pub fn remove(&self, p: &StarrProfile) {
+        let key = (p.host.clone(), p.port, p.user.clone());
+        if let Some(e) = self.sessions.lock().unwrap().remove(&key) {
+            disconnect(&e.session);
+        }
+    }
+
+    /// Sessions schließen, die länger als `ttl` unbenutzt waren.
+    pub fn evict_idle(&self) {
+        self.evict_locked(&mut self.sessions.lock().unwrap());
+    }
+
+    /// Anzahl gehaltener Sessions.
+    pub fn len(&self) -> usize {
+        self.sessions.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn evict_locked(&self, sessions: &mut HashMap<PoolKey, Entry>) {
+        sessions.retain(|_, e| {
+            let keep = e.last_used.elapsed() < self.ttl;
+            if !keep {
+                disconnect(&e.session);
+            }
+            keep
+        });
+    }
+}
+
+impl Drop for SessionPool {
+    fn drop(&mut self) {
+        if let Ok(sessions) = self.sessions.get_mut() {
+            for (_, e) in sessions.drain() {
+                disconnect(&e.session);
+            }
+        }
+    }
+}
+
+/// Höflich abmelden; läuft gerade ein `exec` auf der Session, räumt der Drop auf.
+fn disconnect(s: &PooledSession) {
+    if let Ok(sess) = s.inner.try_lock() {
+        sess.set_blocking(true);
+        let _ = sess.disconnect(None, "idle", None);
+    }
+}
@@ -0,0 +1,280 @@
+//! SFTP über die bestehende Session (eigener Channel, kein Re-Login).
+//! Die Session ist non-blocking → jeder Aufruf läuft über `retry`.
+
+use crate::{retry, write_all_retry};
+use anyhow::{anyhow, Context, Result};
+use std::fs::File;
+use std::io::{ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+/// Übertragungsblockgröße für Upload/Download.
+const CHUNK: usize = 32 * 1024;
+
+/// Ein Eintrag aus `StarrSftp::list`.
+#[derive(Debug, Clone)]
+pub struct SftpEntry {
+    pub path: PathBuf,
+    pub name: String,
+    pub is_dir: bool,
+    pub size: Option<u64>,
+    /// Unix-Rechte (inkl. Typ-Bits), falls der Server sie liefert
+    pub perm: Option<u32>,
+    /// Unix-Timestamp
+    pub mtime: Option<u64>,
+}
+
+impl SftpEntry {
+    /// `ls -l`-Stil, z. B. `drwxr-xr-x`; leer, wenn der Server keine Rechte liefert.
+    pub fn perm_string(&self) -> String {
+        let Some(perm) = self.perm else {
+            return String::new();
+        };
+        let kind = match perm & 0o170_000 {
+            0o040_000 => 'd',
+            0o120_000 => 'l',
+            _ => '-',
+        };
+        let bits = (0..9).rev().map(|i| {
+            if perm & (1 << i) != 0 {
+                b"rwx"[(8 - i) % 3] as char
+            } else {
+                '-'
+            }
+        });
+        std::iter::once(kind).chain(bits).collect()
+    }
+
+    /// Änderungszeit als `YYYY-MM-DD HH:MM` (UTC).
+    pub fn mtime_string(&self) -> Option<String> {
+        let (year, month, day, rem) = crate::transcript::civil_from_unix(self.mtime?);
+        Some(format!(
+            "{year:04}-{month:02}-{day:02} {:02}:{:02}",
+            rem / 3600,
+            rem % 3600 / 60
+        ))
+    }
+}
+
+pub struct StarrSftp {
+    sftp: ssh2::Sftp,
+}
+
+impl StarrSftp {
+    pub(crate) fn new(sess: &ssh2::Session) -> Result<Self> {
+        let sftp = retry(|| sess.sftp()).context("SFTP-Subsystem nicht verfügbar")?;
+        Ok(Self { sftp })
+    }
+
+    /// Lädt `local` nach `remote` hoch. Liefert die Anzahl übertragener Bytes.
+    pub fn upload(&self, local: &Path, remote: &Path) -> Result<u64> {
+        self.upload_with_progress(local, remote, |_, _| {})
+    }
+
+    /// Wie `upload`, ruft `progress(übertragen, gesamt)` nach jedem Block.
+    pub fn upload_with_progress(
+        &self,
+        local: &Path,
+        remote: &Path,
+        mut progress: impl FnMut(u64, u64),
+    ) -> Result<u64> {
+        let mut src =
+            File::open(local).with_context(|| format!("{} nicht lesbar", local.display()))?;
+        let total = src.metadata()?.len();
+        let mut dst = retry(|| self.sftp.create(remote))
+            .with_context(|| format!("{} nicht anlegbar", remote.display()))?;
+
+        let mut buf = vec![0u8; CHUNK];
+        let mut done = 0u64;
+        loop {
+            let n = src.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            write_all_retry(&mut dst, &buf[..n])?;
+            done += n as u64;
+            progress(done, total);
+        }
+        Ok(done)
+    }
+
+    /// Lädt Dateien und Verzeichnisse (rekursiv) nach `remote_dir` hoch, wie `scp -r`.
+    /// Fehlende Verzeichnisse werden angelegt, bestehende Dateien überschrieben.
+    /// `progress(datei, übertragen, gesamt)` zählt über alle Einträge zusammen.
+    pub fn upload_all(
+        &self,
+        locals: &[PathBuf],
+        remote_dir: &Path,
+        mut progress: impl FnMut(&Path, u64, u64),
+    ) -> Result<u64> {
+        let total = locals.iter().map(|p| local_size(p)).sum();
+        let mut done = 0;
+        for local in locals {
+            let name = local
+                .file_name()
+                .ok_or_else(|| anyhow!("{}: kein Dateiname", local.display()))?
+                .to_string_lossy();
+            self.upload_tree(
+                local,
+                &remote_join(remote_dir, &name),
+                &mut done,
+                total,
+                &mut progress,
+            )?;
+        }
+        Ok(done)
+    }
+
+    fn upload_tree(
+        &self,
+        local: &Path,
+        remote: &Path,
+        done: &mut u64,
+        total: u64,
+        progress: &mut dyn FnMut(&Path, u64, u64),
+    ) -> Result<()> {
+        let meta = std::fs::symlink_metadata(local)
+            .with_context(|| format!("{} nicht lesbar", local.display()))?;
+        // Verlinkte Verzeichnisse auslassen – Schleifen wären sonst endlos
+        if meta.is_symlink() && local.is_dir() {
+            return Ok(());
+        }
+        if !meta.is_dir() {
+            let base = *done;
+            *done +=
+                self.upload_with_progress(local, remote, |n, _| progress(local, base + n, total))?;
+            return Ok(());
+        }
+        if retry(|| self.sftp.stat(remote)).is_err() {
+            self.mkdir(remote, 0o755)?;
+        }
+        let entries = std::fs::read_dir(local)
+            .with_context(|| format!("{} nicht lesbar", local.display()))?;
+        for entry in entries {
+            let entry = entry?;
+            let remote = remote_join(remote, &entry.file_name().to_string_lossy());
+            self.upload_tree(&entry.path(), &remote, done, total, progress)?;
+        }
+        Ok(())
+    }
+
+    /// Lädt `remote` nach `local` herunter. Liefert die Anzahl übertragener Bytes.
+    pub fn download(&self, remote: &Path, local: &Path) -> Result<u64> {
+        self.download_with_progress(remote, local, |_, _| {})
+    }
+
+    /// Wie `download`, ruft `progress(übertragen, gesamt)` nach jedem Block.
+    pub fn download_with_progress(
+        &self,
+        remote: &Path,
+        local: &Path,
+        mut progress: impl FnMut(u64, u64),
+    ) -> Result<u64> {
+        let total = retry(|| self.sftp.stat(remote))
+            .with_context(|| format!("{} nicht gefunden", remote.display()))?
+            .size
+            .unwrap_or(0);
+        let mut src = retry(|| self.sftp.open(remote))?;
+        let mut dst =
+            File::create(local).with_context(|| format!("{} nicht anlegbar", local.display()))?;
+
+        let mut buf = vec![0u8; CHUNK];
+        let mut done = 0u64;
+        loop {
+            let n = match src.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(5));
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            dst.write_all(&buf[..n])?;
+            done += n as u64;
+            progress(done, total);
+        }
+        dst.flush()?;
+        Ok(done)
+    }
+
+    /// Verzeichnisinhalt (ohne `.` / `..`).
+    pub fn list(&self, remote: &Path) -> Result<Vec<SftpEntry>> {
+        let entries = retry(|| self.sftp.readdir(remote))
+            .with_context(|| format!("{} nicht lesbar", remote.display()))?;
+        Ok(entries
+            .into_iter()
+            .filter_map(|(path, stat)| {
+                let name = path.file_name()?.to_string_lossy().into_owned();
+                if name == "." || name == ".." {
+                    return None;
+                }
+                Some(SftpEntry {
+                    is_dir: stat.is_dir(),
+                    size: stat.size,
+                    perm: stat.perm,
+                    mtime: stat.mtime,
+                    name,
+                    path,
+                })
+            })
+            .collect())
+    }
+
+    /// Löscht eine Datei oder ein leeres Verzeichnis.
+    pub fn remove(&self, remote: &Path) -> Result<()> {
+        let stat = retry(|| self.sftp.lstat(remote))
+            .with_context(|| format!("{} nicht gefunden", remote.display()))?;
+        let res = if stat.is_dir() {
+            retry(|| self.sftp.rmdir(remote))
+        } else {
+            retry(|| self.sftp.unlink(remote))
+        };
+        res.map_err(|e| anyhow!("{} nicht löschbar: {e}", remote.display()))
+    }
+
+    /// Legt ein Verzeichnis an (`mode` z. B. 0o755).
+    pub fn mkdir(&self, remote: &Path, mode: i32) -> Result<()> {
+        retry(|| self.sftp.mkdir(remote, mode))
+            .map_err(|e| anyhow!("{} nicht anlegbar: {e}", remote.display()))
+    }
+
+    /// Benennt um / verschiebt.
+    pub fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        retry(|| self.sftp.rename(from, to, None))
+            .map_err(|e| anyhow!("{} → {} fehlgeschlagen: {e}", from.display(), to.display()))
+    }
+
+    /// Metadaten eines Pfads.
+    pub fn stat(&self, remote: &Path) -> Result<ssh2::FileStat> {
+        retry(|| self.sftp.stat(remote))
+            .with_context(|| format!("{} nicht gefunden", remote.display()))
+    }
+
+    /// Absoluter Pfad auf dem Server (`.` → Home-Verzeichnis).
+    pub fn realpath(&self, remote: &Path) -> Result<PathBuf> {
+        retry(|| self.sftp.realpath(remote))
+            .with_context(|| format!("{} nicht gefunden", remote.display()))
+    }
+}
+
+/// Remote-Pfade immer mit `/` – `Path::join` nähme unter Windows `\`.
+fn remote_join(dir: &Path, name: &str) -> PathBuf {
+    let dir = dir.to_string_lossy();
+    PathBuf::from(format!("{}/{name}", dir.trim_end_matches('/')))
+}
+
+/// Bytes unter `path` (rekursiv, ohne verlinkte Verzeichnisse); Unlesbares zählt 0.
+fn local_size(path: &Path) -> u64 {
+    match std::fs::symlink_metadata(path) {
+        Ok(m) if m.is_dir() => std::fs::read_dir(path)
+            .map(|entries| entries.flatten().map(|e| local_size(&e.path())).sum())
+            .unwrap_or(0),
+        Ok(m) if m.is_symlink() => std::fs::metadata(path)
+            .map(|m| if m.is_dir() { 0 } else { m.len() })
+            .unwrap_or(0),
+        Ok(m) => m.len(),
+        Err(_) => 0,
+    }
+}
@@ -0,0 +1,311 @@
+//! Import aus OpenSSH `~/.ssh/config`: `Host`-Blöcke → `SavedProfile`.
+//! Unterstützt HostName, Port, User, IdentityFile, ProxyJump; alles andere wird übersprungen.
+
+use crate::{SavedProfile, StarrProfile};
+use anyhow::{Context, Result};
+use directories::BaseDirs;
+use std::path::{Path, PathBuf};
+
+/// Standardpfad `~/.ssh/config`.
+pub fn default_ssh_config_path() -> Option<PathBuf> {
+    BaseDirs::new().map(|b| b.home_dir().join(".ssh").join("config"))
+}
+
+/// Liest eine ssh_config und liefert ein Profil je konkretem Host-Alias
+/// (Muster mit `*`/`?` dienen nur als Defaults für passende Aliase).
+pub fn parse_ssh_config(path: &Path) -> Result<Vec<SavedProfile>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("{} nicht lesbar", path.display()))?;
+    parse_ssh_config_str(&text)
+}
+
+/// Wie `parse_ssh_config`, aus dem Dateiinhalt. Eine kaputte `ProxyJump`-Angabe ist ein
+/// Fehler – sonst verbände das importierte Profil still direkt statt über den Jump-Host.
+pub fn parse_ssh_config_str(text: &str) -> Result<Vec<SavedProfile>> {
+    let blocks = parse_blocks(text);
+
+    let mut aliases: Vec<&str> = Vec::new();
+    for b in &blocks {
+        for p in &b.patterns {
+            if !p.starts_with('!') && !p.contains(['*', '?']) && !aliases.contains(&p.as_str()) {
+                aliases.push(p);
+            }
+        }
+    }
+
+    aliases
+        .into_iter()
+        .map(|alias| {
+            let (mut p, proxy_jump) = resolve_alias(&blocks, alias);
+            if p.user.is_empty() {
+                p.user = whoami_fallback();
+            }
+            // Erst jetzt: die Hops erben den fertigen User
+            if let Some(spec) = proxy_jump {
+                p.set_jump_hosts(&spec)
+                    .with_context(|| format!("Host {alias}: ProxyJump '{spec}'"))?;
+            }
+            Ok(SavedProfile::new(alias, p))
+        })
+        .collect()
+}
+
+/// Profil für `alias`, wie `ssh alias` es sähe (für die Kommandozeile). `None`, wenn
+/// kein `Host`-Block den Namen nennt oder per Muster trifft – `Host *` allein zählt nicht.
+/// Ohne `User` in der Config bleibt `user` leer, damit der Aufrufer seinen Default nimmt.
+/// `ProxyJump` kommt als Angabe für `set_jump_hosts` mit, anzuwenden, wenn das Profil
+/// fertig ist (User, Passwort, …) – die Hops erben davon.
+pub fn resolve_ssh_host(text: &str, alias: &str) -> Option<(StarrProfile, Option<String>)> {
+    let blocks = parse_blocks(text);
+    let defined = blocks
+        .iter()
+        .skip(1)
+        .any(|b| b.patterns != ["*"] && block_matches(b, alias));
+    defined.then(|| resolve_alias(&blocks, alias))
+}
+
+#[derive(Default)]
+struct Block {
+    patterns: Vec<String>,
+    /// (Direktive in Kleinbuchstaben, Wert)
+    options: Vec<(String, String)>,
+}
+
+fn parse_blocks(text: &str) -> Vec<Block> {
+    // Optionen vor dem ersten `Host` gelten für alle
+    let mut blocks = vec![Block {
+        patterns: vec!["*".into()],
+        ..Default::default()
+    }];
+    let mut in_match = false;
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        // `Key Wert` oder `Key=Wert`
+        let sep = |c: char| c.is_whitespace() || c == '=';
+        let Some((key, value)) = line.split_once(sep) else {
+            continue;
+        };
+        let (key, value) = (
+            key.to_ascii_lowercase(),
+            value.trim_start_matches(sep).trim(),
+        );
+        match key.as_str() {
+            "host" => {
+                in_match = false;
+                blocks.push(Block {
+                    patterns: value.split_whitespace().map(str::to_string).collect(),
+                    ..Default::default()
+                });
+            }
+            // Match-Blöcke können wir nicht auswerten → bis zum nächsten Host ignorieren
+            "match" => in_match = true,
+            _ if in_match => {}
+            _ => {
+                let value = value.trim_matches('"').to_string();
+                blocks
+                    .last_mut()
+                    .expect("mindestens ein Block")
+                    .options
+                    .push((key, value));
+            }
+        }
+    }
+    blocks
+}
+
+/// OpenSSH-Semantik: der erste Wert je Direktive aus passenden Blöcken gewinnt
+/// (außer `IdentityFile`: alle zählen). Ohne `User` bleibt `user` leer;
+/// `ProxyJump` (außer `none`) kommt getrennt zurück.
+fn resolve_alias(blocks: &[Block], alias: &str) -> (StarrProfile, Option<String>) {
+    let mut host = None;
+    let mut port = None;
+    let mut user = None;
+    // IdentityFile darf mehrfach vorkommen – alle sammeln, in Reihenfolge
+    let mut identities = Vec::new();
+    let mut proxy_jump = None;
+
+    for b in blocks.iter().filter(|b| block_matches(b, alias)) {
+        for (key, value) in &b.options {
+            let slot = match key.as_str() {
+                "hostname" => &mut host,
+                "port" => &mut port,
+                "user" => &mut user,
+                "identityfile" => {
+                    identities.push(expand_tilde(value));
+                    continue;
+                }
+                "proxyjump" => &mut proxy_jump,
+                _ => continue,
+            };
+            if slot.is_none() {
+                *slot = Some(value.clone());
+            }
+        }
+    }
+
+    let p = StarrProfile {
+        host: host.map_or_else(|| alias.to_string(), |h| h.replace("%h", alias)),
+        port: port.and_then(|p| p.parse().ok()).unwrap_or(22),
+        user: user.unwrap_or_default(),
+        key_path: identities.first().cloned(),
+        identities: identities.into_iter().skip(1).collect(),
+        ..Default::default()
+    };
+    (p, proxy_jump.filter(|s| !s.eq_ignore_ascii_case("none")))
+}
+
+fn block_matches(b: &Block, alias: &str) -> bool {
+    let mut matched = false;
+    for pat in &b.patterns {
+        match pat.strip_prefix('!') {
+            Some(neg) if glob(neg, alias) => return false,
+            Some(_) => {}
+            None => matched |= glob(pat, alias),
+        }
+    }
+    matched
+}
+
+/// ssh_config-Muster: `*` beliebig viele, `?` genau ein Zeichen.
+fn glob(pattern: &str, s: &str) -> bool {
+    let (p, s): (Vec<char>, Vec<char>) = (pattern.chars().collect(), s.chars().collect());
+    let (mut pi, mut si) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while si < s.len() {
+        match p.get(pi) {
+            Some('*') => {
+                star = Some((pi, si));
+                pi += 1;
+            }
+            Some(&c) if c == '?' || c.eq_ignore_ascii_case(&s[si]) => {
+                pi += 1;
+                si += 1;
+            }
+            _ => match star {
+                Some((sp, ss)) => {
+                    pi = sp + 1;
+                    si = ss + 1;
+                    star = Some((sp, ss + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+fn expand_tilde(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), BaseDirs::new()) {
+        (Some(rest), Some(b)) => b.home_dir().join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Ohne `User` nimmt OpenSSH den lokalen Benutzer.
+fn whoami_fallback() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "\
+# globale Defaults
+User fallback
+
+Host web web-?
+    HostName 10.0.0.5
+    Port 2222
+    IdentityFile /keys/web
+    IdentityFile /keys/common
+
+Host *.intern !secret.intern
+    User admin
+    Port=2200
+
+Host db.intern
+    HostName db1.intern
+    ProxyJump ops@bastion:2022,gw
+
+Host *
+    IdentityFile /keys/default
+";
+
+    #[test]
+    fn parse_snippet() {
+        let profiles = parse_ssh_config_str(CONFIG).unwrap();
+        let names: Vec<&str> = profiles.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["web", "db.intern"]);
+
+        let web = &profiles[0].profile;
+        assert_eq!(
+            (web.host.as_str(), web.port, web.user.as_str()),
+            ("10.0.0.5", 2222, "fallback")
+        );
+        assert_eq!(web.key_path.as_deref(), Some(Path::new("/keys/web")));
+        assert_eq!(
+            web.identities,
+            [
+                PathBuf::from("/keys/common"),
+                PathBuf::from("/keys/default")
+            ]
+        );
+
+        // Wildcard-Block liefert User/Port, der erste Treffer gewinnt (globales User zuerst)
+        let db = &profiles[1].profile;
+        assert_eq!(
+            (db.host.as_str(), db.port, db.user.as_str()),
+            ("db1.intern", 2200, "fallback")
+        );
+        // ProxyJump: äußerster Hop zuerst; ohne `user@` erbt der Hop den User des Profils
+        let gw = db.jump.as_deref().expect("innerer Hop");
+        assert_eq!(
+            (gw.host.as_str(), gw.port, gw.user.as_str()),
+            ("gw", 22, "fallback")
+        );
+        let bastion = gw.jump.as_deref().expect("äußerer Hop");
+        assert_eq!(
+            (bastion.host.as_str(), bastion.port, bastion.user.as_str()),
+            ("bastion", 2022, "ops")
+        );
+        assert!(bastion.jump.is_none());
+        assert!(profiles[0].profile.jump.is_none());
+    }
+
+    #[test]
+    fn proxy_jump_hop_gets_default_user() {
+        let profiles = parse_ssh_config_str("Host app\n  ProxyJump bastion\n").unwrap();
+        let p = &profiles[0].profile;
+        assert_eq!(p.user, whoami_fallback());
+        assert_eq!(
+            p.jump.as_deref().map(|j| j.user.as_str()),
+            Some(p.user.as_str())
+        );
+    }
+
+    #[test]
+    fn broken_proxy_jump_is_an_error() {
+        let err = parse_ssh_config_str("Host app\n  ProxyJump [::1\n").unwrap_err();
+        assert!(format!("{err:#}").contains("Host app"), "{err:#}");
+    }
+
+    #[test]
+    fn resolve_wildcard_and_negation() {
+        let (app, jump) = resolve_ssh_host(CONFIG, "app.intern").expect("über *.intern definiert");
+        assert_eq!((app.host.as_str(), app.port), ("app.intern", 2200));
+        assert!(jump.is_none() && app.jump.is_none());
+        let (_, jump) = resolve_ssh_host(CONFIG, "db.intern").unwrap();
+        assert_eq!(jump.as_deref(), Some("ops@bastion:2022,gw"));
+        assert!(resolve_ssh_host(CONFIG, "web-1").is_some());
+        // `!secret.intern` schließt aus, `Host *` allein zählt nicht
+        assert!(resolve_ssh_host(CONFIG, "secret.intern").is_none());
+        assert!(resolve_ssh_host(CONFIG, "example.com").is_none());
+    }
+}
@@ -0,0 +1,963 @@
+//! Terminal-Emulation: Zellen-Grid mit Cursor und Scrollback, gefüttert mit dem Shell-Output.
+//! Deckt die üblichen Steuerzeichen und CSI-Sequenzen ab (CUP, ED, EL, SGR, Scroll-Region,
+//! Alternate Screen, Maus-Modi, Autowrap, OSC 52) – genug für top/vim/less/tmux, kein vollständiger xterm.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Obergrenze für numerische CSI-Parameter (wie bei xterm ein 16-Bit-Wert).
+const MAX_CSI_PARAM: usize = u16::MAX as usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TermColor {
+    /// Vorder-/Hintergrund des Frontends
+    #[default]
+    Default,
+    /// 0–15 Basisfarben, 16–255 xterm-256-Palette
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct CellAttrs {
+    pub fg: TermColor,
+    pub bg: TermColor,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub reverse: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub ch: char,
+    pub attrs: CellAttrs,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self {
+            ch: ' ',
+            attrs: CellAttrs::default(),
+        }
+    }
+}
+
+impl Cell {
+    /// Leerzeichen ohne Attribute – am Zeilenende abschneidbar.
+    pub fn is_blank(&self) -> bool {
+        self.ch == ' ' && self.attrs == CellAttrs::default()
+    }
+}
+
+/// Zusammenhängender Text mit gleichen Attributen – fertig zum Zeichnen, ohne Parser.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    pub text: String,
+    pub attrs: CellAttrs,
+    /// Cursor steht auf diesem Span (dann genau ein Zeichen)
+    pub cursor: bool,
+}
+
+/// Momentaufnahme des sichtbaren Grids als Spans (vom Parser-Thread an die Oberfläche).
+/// Den Scrollback holt sich das Frontend über `scrolled_lines`/`lines` selbst –
+/// dessen Zeilen ändern sich nicht mehr.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Frame {
+    /// Fortlaufend je `Screen`; ältere Frames, die später ankommen, verwerfen
+    pub seq: u64,
+    /// `Screen::scrolled_lines` beim Erfassen
+    pub scrolled: u64,
+    /// Eine Zeile je Grid-Zeile, ohne Leerraum am Ende
+    pub grid: Vec<Vec<Span>>,
+}
+
+/// Zeile in Spans zerlegen; `cursor`: Spalte mit Cursor (bekommt einen eigenen Span).
+pub fn line_spans(line: &[Cell], cursor: Option<usize>) -> Vec<Span> {
+    let mut end = line
+        .iter()
+        .rposition(|c| !c.is_blank())
+        .map_or(0, |p| p + 1);
+    if let Some(c) = cursor {
+        end = end.max(c + 1).min(line.len());
+    }
+    let mut spans: Vec<Span> = Vec::new();
+    for (col, cell) in line[..end].iter().enumerate() {
+        let is_cursor = cursor == Some(col);
+        match spans.last_mut() {
+            Some(s) if s.attrs == cell.attrs && s.cursor == is_cursor => s.text.push(cell.ch),
+            _ => spans.push(Span {
+                text: cell.ch.to_string(),
+                attrs: cell.attrs,
+                cursor: is_cursor,
+            }),
+        }
+    }
+    spans
+}
+
+/// Vom Remote per DECSET angeforderte Maus-Meldungen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MouseMode {
+    #[default]
+    Off,
+    /// 1000: nur Drücken/Loslassen (+ Mausrad)
+    Press,
+    /// 1002: zusätzlich Bewegung bei gedrückter Taste
+    Drag,
+    /// 1003: jede Bewegung
+    Motion,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+    /// Bewegung ohne gedrückte Taste
+    None,
+    WheelUp,
+    WheelDown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseAction {
+    Press,
+    Release,
+    Move,
+}
+
+/// Längere OSC-Sequenzen werden abgeschnitten.
+const MAX_OSC_LEN: usize = 4096;
+/// OSC 52 (Zwischenablage) darf länger sein: base64-Text bis hier, längere werden verworfen.
+const MAX_OSC52_LEN: usize = 1 << 20;
+
+fn osc_limit(osc: &str) -> usize {
+    if osc.starts_with("52;") {
+        MAX_OSC52_LEN
+    } else {
+        MAX_OSC_LEN
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Ground,
+    Escape,
+    Csi,
+    Osc,
+    OscEscape,
+    /// `ESC (` u. ä.: ein Zeichen (Zeichensatz) überspringen
+    Charset,
+    /// DCS/SOS/PM/APC: bis ST verwerfen
+    Ignore,
+    IgnoreEscape,
+}
+
+/// Hauptbildschirm, solange der Alternate Screen (vim, less, …) aktiv ist.
+struct SavedMain {
+    grid: Vec<Vec<Cell>>,
+    cursor: (usize, usize),
+}
+
+pub struct Screen {
+    cols: usize,
+    rows: usize,
+    grid: Vec<Vec<Cell>>,
+    scrollback: VecDeque<Vec<Cell>>,
+    max_scrollback: usize,
+    /// Zeilen, die je in den Scrollback gewandert sind (auch über Reset hinweg)
+    scrolled: u64,
+    /// Zähler für `Frame::seq` (auch über Reset hinweg)
+    frames: u64,
+    row: usize,
+    col: usize,
+    /// Letzte Spalte beschrieben → nächstes Zeichen bricht erst um (xterm-Verhalten)
+    wrap_pending: bool,
+    /// DECAWM (`?7h`, Standard an): am rechten Rand umbrechen statt die letzte Spalte zu überschreiben
+    autowrap: bool,
+    attrs: CellAttrs,
+    saved_cursor: (usize, usize, CellAttrs),
+    /// Scroll-Region (DECSTBM), inklusiv
+    top: usize,
+    bottom: usize,
+    cursor_visible: bool,
+    alt: Option<SavedMain>,
+    /// DECCKM (`?1h`): Cursortasten als `ESC O A` statt `ESC [ A`
+    app_cursor: bool,
+    mouse_mode: MouseMode,
+    /// 1006: SGR-Kodierung statt X10-Bytes
+    mouse_sgr: bool,
+    /// 2004: Eingefügtes in `ESC[200~ … ESC[201~` einrahmen
+    bracketed_paste: bool,
+    /// Vom Remote gesetzter Titel (OSC 0/2)
+    title: String,
+    /// Per OSC 52 gesetzter Text, noch nicht abgeholt
+    clipboard: Option<String>,
+    /// BEL gesehen, noch nicht abgeholt
+    bell: bool,
+    state: State,
+    params: String,
+    /// Inhalt der laufenden OSC-Sequenz
+    osc: String,
+    /// Laufende OSC war zu lang und ist abgeschnitten
+    osc_truncated: bool,
+}
+
+impl Screen {
+    pub fn new(cols: usize, rows: usize, max_scrollback: usize) -> Self {
+        let (cols, rows) = (cols.max(1), rows.max(1));
+        Self {
+            cols,
+            rows,
+            grid: vec![vec![Cell::default(); cols]; rows],
+            scrollback: VecDeque::new(),
+            max_scrollback,
+            scrolled: 0,
+            frames: 0,
+            row: 0,
+            col: 0,
+            wrap_pending: false,
+            autowrap: true,
+            attrs: CellAttrs::default(),
+            saved_cursor: (0, 0, CellAttrs::default()),
+            top: 0,
+            bottom: rows - 1,
+            cursor_visible: true,
+            alt: None,
+            app_cursor: false,
+            mouse_mode: MouseMode::Off,
+            mouse_sgr: false,
+            bracketed_paste: false,
+            title: String::new(),
+            clipboard: None,
+            bell: false,
+            state: State::Ground,
+            params: String::new(),
+            osc: String::new(),
+            osc_truncated: false,
+        }
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// (Zeile, Spalte) im sichtbaren Grid.
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.row, self.col)
+    }
+
+    pub fn cursor_visible(&self) -> bool {
+        self.cursor_visible
+    }
+
+    /// Alternate Screen aktiv (vim, less, man, …).
+    pub fn alt_screen(&self) -> bool {
+        self.alt.is_some()
+    }
+
+    pub fn app_cursor_keys(&self) -> bool {
+        self.app_cursor
+    }
+
+    /// Fenstertitel per `ESC]0;…` / `ESC]2;…`; leer, solange keiner gesetzt wurde.
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Text, den der Remote per OSC 52 (`ESC]52;c;<base64>`) in die Zwischenablage legen will.
+    /// Einmal abholbar; ob er dort wirklich landet, entscheidet das Frontend.
+    pub fn take_clipboard(&mut self) -> Option<String> {
+        self.clipboard.take()
+    }
+
+    /// `true`, wenn seit dem letzten Aufruf ein BEL (`\x07`) kam.
+    pub fn take_bell(&mut self) -> bool {
+        std::mem::take(&mut self.bell)
+    }
+
+    pub fn mouse_mode(&self) -> MouseMode {
+        self.mouse_mode
+    }
+
+    pub fn bracketed_paste(&self) -> bool {
+        self.bracketed_paste
+    }
+
+    /// Kodiert ein Maus-Ereignis (Zelle 0-basiert) so, wie der Remote es angefordert hat;
+    /// `None`, wenn der aktuelle Modus das Ereignis nicht meldet.
+    pub fn mouse_report(
+        &self,
+        button: MouseButton,
+        action: MouseAction,
+        col: usize,
+        row: usize,
+    ) -> Option<String> {
+        let wanted = match (self.mouse_mode, action, button) {
+            (MouseMode::Off, ..) => false,
+            (_, MouseAction::Press | MouseAction::Release, MouseButton::None) => false,
+            (_, MouseAction::Release, MouseButton::WheelUp | MouseButton::WheelDown) => false,
+            (_, MouseAction::Press | MouseAction::Release, _) => true,
+            (MouseMode::Drag, MouseAction::Move, b) => b != MouseButton::None,
+            (MouseMode::Motion, MouseAction::Move, _) => true,
+            (MouseMode::Press, MouseAction::Move, _) => false,
+        };
+        if !wanted {
+            return None;
+        }
+        let mut cb = match button {
+            MouseButton::Left => 0,
+            MouseButton::Middle => 1,
+            MouseButton::Right => 2,
+            MouseButton::None => 3,
+            MouseButton::WheelUp => 64,
+            MouseButton::WheelDown => 65,
+        };
+        if action == MouseAction::Move {
+            cb += 32;
+        }
+        let (x, y) = (col.min(self.cols - 1) + 1, row.min(self.rows - 1) + 1);
+        if self.mouse_sgr {
+            let fin = if action == MouseAction::Release {
+                'm'
+            } else {
+                'M'
+            };
+            return Some(format!("\x1b[<{cb};{x};{y}{fin}"));
+        }
+        // X10: Loslassen ohne Tastennummer, Werte als Byte 32+v. Wir senden Text,
+        // also nur bis 126 (ASCII) – größere Fenster brauchen 1006.
+        if action == MouseAction::Release {
+            cb = 3;
+        }
+        let enc = |v: usize| (v <= 94).then(|| char::from(32 + v as u8));
+        Some(format!("\x1b[M{}{}{}", enc(cb)?, enc(x)?, enc(y)?))
+    }
+
+    pub fn scrollback_len(&self) -> usize {
+        self.scrollback.len()
+    }
+
+    /// Fortlaufende Zählung aller Zeilen, die in den Scrollback gewandert sind.
+    /// Scrollback-Zeile `i` hat die Nummer `scrolled_lines() - scrollback_len() + i`;
+    /// einmal dort, ändert sich eine Zeile nicht mehr → Frontends können sie cachen.
+    pub fn scrolled_lines(&self) -> u64 {
+        self.scrolled
+    }
+
+    pub fn max_scrollback(&self) -> usize {
+        self.max_scrollback
+    }
+
+    /// Neues Scrollback-Limit; überzählige älteste Zeilen fallen sofort weg.
+    pub fn set_max_scrollback(&mut self, lines: usize) {
+        self.max_scrollback = lines;
+        let excess = self.scrollback.len().saturating_sub(lines);
+        self.scrollback.drain(..excess);
+    }
+
+    /// Sichtbaren Bereich leeren, Cursor nach oben links (wie `ESC[H ESC[2J`).
+    pub fn clear_screen(&mut self) {
+        let (rows, cols) = (self.rows, self.cols);
+        (0..rows).for_each(|r| self.clear_cells(r, 0, cols));
+        self.row = 0;
+        self.col = 0;
+        self.wrap_pending = false;
+    }
+
+    pub fn clear_scrollback(&mut self) {
+        self.scrollback.clear();
+    }
+
+    /// Alle Zeilen: erst Scrollback (älteste zuerst), dann das sichtbare Grid.
+    pub fn lines(&self) -> impl Iterator<Item = &[Cell]> {
+        self.scrollback
+            .iter()
+            .chain(self.grid.iter())
+            .map(Vec::as_slice)
+    }
+
+    /// Sichtbares Grid als Spans, inkl. Cursor (falls sichtbar).
+    pub fn frame(&mut self) -> Frame {
+        self.frames += 1;
+        let cursor_row = self.cursor_visible.then_some(self.row);
+        let grid = self
+            .grid
+            .iter()
+            .enumerate()
+            .map(|(r, line)| line_spans(line, (cursor_row == Some(r)).then_some(self.col)))
+            .collect();
+        Frame {
+            seq: self.frames,
+            scrolled: self.scrolled,
+            grid,
+        }
+    }
+
+    /// Reiner Text (Zeilen ohne abschließende Leerzeichen), z. B. fürs Kopieren.
+    pub fn text(&self) -> String {
+        let mut out = String::new();
+        for (i, line) in self.lines().enumerate() {
+            if i > 0 {
+                out.push('\n');
+            }
+            let end = line.iter().rposition(|c| c.ch != ' ').map_or(0, |p| p + 1);
+            out.extend(line[..end].iter().map(|c| c.ch));
+        }
+        out
+    }
+
+    /// Output der Shell verarbeiten.
+    pub fn feed(&mut self, s: &str) {
+        for ch in s.chars() {
+            self.step(ch);
+        }
+    }
+
+    /// Neue Größe; überzählige Zeilen oberhalb des Cursors wandern in den Scrollback.
+    pub fn resize(&mut self, cols: usize, rows: usize) {
+        let (cols, rows) = (cols.max(1), rows.max(1));
+        if cols == self.cols && rows == self.rows {
+            return;
+        }
+        for line in &mut self.grid {
+            line.resize(cols, Cell::default());
+        }
+        // Beim Verkleinern oben abschneiden, damit die Cursorzeile sichtbar bleibt.
+        // Zeilen des Alternate Screens gehören nicht in den Scrollback.
+        let in_alt = self.alt.is_some();
+        while self.grid.len() > rows {
+            if self.row > 0 {
+                let line = self.grid.remove(0);
+                if !in_alt {
+                    self.push_scrollback(line);
+                }
+                self.row -= 1;
+            } else {
+                self.grid.pop();
+            }
+        }
+        while self.grid.len() < rows {
+            self.grid.push(vec![Cell::default(); cols]);
+        }
+        // Gesicherter Hauptbildschirm genauso, sonst ist nach `vim` der Prompt weg
+        let mut evicted = Vec::new();
+        if let Some(main) = self.alt.as_mut() {
+            for line in &mut main.grid {
+                line.resize(cols, Cell::default());
+            }
+            while main.grid.len() > rows {
+                if main.cursor.0 > 0 {
+                    evicted.push(main.grid.remove(0));
+                    main.cursor.0 -= 1;
+                } else {
+                    main.grid.pop();
+                }
+            }
+            main.grid.resize(rows, vec![Cell::default(); cols]);
+        }
+        for line in evicted {
+            self.push_scrollback(line);
+        }
+        self.cols = cols;
+        self.rows = rows;
+        self.top = 0;
+        self.bottom = rows - 1;
+        self.row = self.row.min(rows - 1);
+        self.col = self.col.min(cols - 1);
+        self.wrap_pending = false;
+    }
+
+    fn step(&mut self, ch: char) {
+        match self.state {
+            State::Ground => self.ground(ch),
+            State::Escape => {
+                self.state = State::Ground;
+                match ch {
+                    '[' => {
+                        self.params.clear();
+                        self.state = State::Csi;
+                    }
+                    ']' => {
+                        self.osc.clear();
+                        self.state = State::Osc;
+                    }
+                    '(' | ')' | '*' | '+' | '#' | '%' => self.state = State::Charset,
+                    'P' | 'X' | '^' | '_' => self.state = State::Ignore,
+                    '7' => self.saved_cursor = (self.row, self.col, self.attrs),
+                    '8' => self.restore_cursor(),
+                    'D' => self.linefeed(),
+                    'E' => {
+                        self.col = 0;
+                        self.linefeed();
+                    }
+                    'M' => self.reverse_index(),
+                    'c' => {
+                        let (scrolled, frames) = (self.scrolled, self.frames);
+                        *self = Self::new(self.cols, self.rows, self.max_scrollback);
+                        (self.scrolled, self.frames) = (scrolled, frames);
+                    }
+                    _ => {}
+                }
+            }
+            State::Csi => match ch {
+                // Parameter (0x30–0x3F) und Zwischenzeichen (0x20–0x2F)
+                ' '..='?' => self.params.push(ch),
+                '@'..='~' => {
+                    self.state = State::Ground;
+                    self.csi(ch);
+                }
+                '\x1b' => self.state = State::Escape,
+                // Steuerzeichen mitten in CSI werden ausgeführt (VT-Verhalten)
+                c if c < ' ' => self.ground(c),
+                _ => self.state = State::Ground,
+            },
+            // OSC bis BEL oder ST (ESC \) sammeln
+            State::Osc => match ch {
+                '\x07' => {
+                    self.state = State::Ground;
+                    self.osc_dispatch();
+                }
+                '\x1b' => self.state = State::OscEscape,
+                // Schutz gegen endlose Sequenzen (z. B. `cat` auf Binärdaten)
+                _ if self.osc.len() >= osc_limit(&self.osc) => self.osc_truncated = true,
+                _ => self.osc.push(ch),
+            },
+            State::OscEscape => {
+                self.state = State::Ground;
+                self.osc_dispatch();
+                // ESC ohne `\` beendet die OSC und beginnt gleich die nächste Sequenz
+                if ch != '\\' {
+                    self.state = State::Escape;
+                    self.step(ch);
+                }
+            }
+            State::Charset => self.state = State::Ground,
+            State::Ignore => match ch {
+                '\x07' | '\u{9c}' => self.state = State::Ground,
+                '\x1b' => self.state = State::IgnoreEscape,
+                _ => {}
+            },
+            State::IgnoreEscape => {
+                self.state = State::Ground;
+                if ch != '\\' {
+                    self.state = State::Escape;
+                    self.step(ch);
+                }
+            }
+        }
+    }
+
+    fn ground(&mut self, ch: char) {
+        match ch {
+            '\x1b' => self.state = State::Escape,
+            '\r' => {
+                self.col = 0;
+                self.wrap_pending = false;
+            }
+            '\n' | '\x0b' | '\x0c' => self.linefeed(),
+            '\x07' => self.bell = true,
+            // 8-Bit-C1-Steuerzeichen
+            '\u{9b}' => {
+                self.params.clear();
+                self.state = State::Csi;
+            }
+            '\u{9d}' => {
+                self.osc.clear();
+                self.state = State::Osc;
+            }
+            '\u{90}' | '\u{98}' | '\u{9e}' | '\u{9f}' => self.state = State::Ignore,
+            '\x08' => {
+                self.col = self.col.saturating_sub(1);
+                self.wrap_pending = false;
+            }
+            '\t' => {
+                self.col = ((self.col / 8 + 1) * 8).min(self.cols - 1);
+                self.wrap_pending = false;
+            }
+            c if c < ' ' || ('\x7f'..='\u{9f}').contains(&c) => {}
+            c => self.print(c),
+        }
+    }
+
+    fn print(&mut self, ch: char) {
+        if self.wrap_pending {
+            self.wrap_pending = false;
+            self.col = 0;
+            self.linefeed();
+        }
+        self.grid[self.row][self.col] = Cell {
+            ch,
+            attrs: self.attrs,
+        };
+        if self.col + 1 >= self.cols {
+            // Ohne Autowrap bleibt der Cursor stehen, weitere Zeichen überschreiben die letzte Spalte
+            self.wrap_pending = self.autowrap;
+        } else {
+            self.col += 1;
+        }
+    }
+
+    fn linefeed(&mut self) {
+        self.wrap_pending = false;
+        if self.row == self.bottom {
+            self.scroll_up(1);
+        } else if self.row + 1 < self.rows {
+            self.row += 1;
+        }
+    }
+
+    fn reverse_index(&mut self) {
+        self.wrap_pending = false;
+        if self.row == self.top {
+            self.scroll_down(1);
+        } else {
+            self.row = self.row.saturating_sub(1);
+        }
+    }
+
+    fn blank_line(&self) -> Vec<Cell> {
+        vec![Cell::default(); self.cols]
+    }
+
+    fn push_scrollback(&mut self, line: Vec<Cell>) {
+        if self.max_scrollback == 0 {
+            return;
+        }
+        if self.scrollback.len() >= self.max_scrollback {
+            self.scrollback.pop_front();
+        }
+        self.scrollback.push_back(line);
+        self.scrolled += 1;
+    }
+
+    /// Region um `n` Zeilen nach oben; oben rausfallende Zeilen gehen in den Scrollback,
+    /// sofern die Region am Bildschirmanfang beginnt und kein Alternate Screen aktiv ist.
+    fn scroll_up(&mut self, n: usize) {
+        for _ in 0..n.min(self.bottom - self.top + 1) {
+            let line = self.grid.remove(self.top);
+            if self.top == 0 && self.alt.is_none() {
+                self.push_scrollback(line);
+            }
+            self.grid.insert(self.bottom, self.blank_line());
+        }
+    }
+
+    fn scroll_down(&mut self, n: usize) {
+        for _ in 0..n.min(self.bottom - self.top + 1) {
+            self.grid.remove(self.bottom);
+            self.grid.insert(self.top, self.blank_line());
+        }
+    }
+
+    fn restore_cursor(&mut self) {
+        let (row, col, attrs) = self.saved_cursor;
+        self.row = row.min(self.rows - 1);
+        self.col = col.min(self.cols - 1);
+        self.attrs = attrs;
+        self.wrap_pending = false;
+    }
+
+    fn clear_cells(&mut self, row: usize, from: usize, to: usize) {
+        let to = to.min(self.cols);
+        for cell in &mut self.grid[row][from.min(to)..to] {
+            *cell = Cell::default();
+        }
+    }
+
+    fn set_alt_screen(&mut self, on: bool) {
+        match (on, self.alt.is_some()) {
+            (true, false) => {
+                let grid = std::mem::replace(
+                    &mut self.grid,
+                    vec![vec![Cell::default(); self.cols]; self.rows],
+                );
+                self.alt = Some(SavedMain {
+                    grid,
+                    cursor: (self.row, self.col),
+                });
+            }
+            (false, true) => {
+                let main = self.alt.take().expect("geprüft");
+                self.grid = main.grid;
+                self.row = main.cursor.0.min(self.rows - 1);
+                self.col = main.cursor.1.min(self.cols - 1);
+            }
+            _ => {}
+        }
+        self.wrap_pending = false;
+    }
+
+    fn csi(&mut self, fin: char) {
+        // Sequenzen mit Zwischenzeichen oder `>`/`=`/`<` (z. B. `CSI > 4;1 m`) gehören
+        // nicht zu den hier umgesetzten – verwerfen statt falsch deuten.
+        if self.params.starts_with(['>', '=', '<'])
+            || self.params.contains(|c| matches!(c, ' '..='/'))
+        {
+            return;
+        }
+        let private = self.params.starts_with('?');
+        let nums: Vec<usize> = self
+            .params
+            .trim_start_matches('?')
+            .split(';')
+            // Vom Remote bestimmt: begrenzen, damit Cursor-Arithmetik nicht überläuft
+            .map(|p| match p.parse::<usize>() {
+                Ok(n) => n.min(MAX_CSI_PARAM),
+                Err(e) if *e.kind() == std::num::IntErrorKind::PosOverflow => MAX_CSI_PARAM,
+                Err(_) => 0,
+            })
+            .collect();
+        // 0 bzw. fehlend → Default (meist 1)
+        let arg =
+            |i: usize, default: usize| nums.get(i).copied().filter(|&n| n != 0).unwrap_or(default);
+
+        if fin != 'm' {
+            self.wrap_pending = false;
+        }
+        let (rows, cols) = (self.rows, self.cols);
+        match fin {
+            'A' => self.row = self.row.saturating_sub(arg(0, 1)),
+            'B' => self.row = self.row.saturating_add(arg(0, 1)).min(rows - 1),
+            'C' => self.col = self.col.saturating_add(arg(0, 1)).min(cols - 1),
+            'D' => self.col = self.col.saturating_sub(arg(0, 1)),
+            'E' => {
+                self.row = self.row.saturating_add(arg(0, 1)).min(rows - 1);
+                self.col = 0;
+            }
+            'F' => {
+                self.row = self.row.saturating_sub(arg(0, 1));
+                self.col = 0;
+            }
+            'G' | '`' => self.col = (arg(0, 1) - 1).min(cols - 1),
+            'd' => self.row = (arg(0, 1) - 1).min(rows - 1),
+            'H' | 'f' => {
+                self.row = (arg(0, 1) - 1).min(rows - 1);
+                self.col = (arg(1, 1) - 1).min(cols - 1);
+            }
+            'J' => match nums.first().copied().unwrap_or(0) {
+                0 => {
+                    self.clear_cells(self.row, self.col, cols);
+                    for r in self.row + 1..rows {
+                        self.clear_cells(r, 0, cols);
+                    }
+                }
+                1 => {
+                    for r in 0..self.row {
+                        self.clear_cells(r, 0, cols);
+                    }
+                    self.clear_cells(self.row, 0, self.col + 1);
+                }
+                2 => (0..rows).for_each(|r| self.clear_cells(r, 0, cols)),
+                3 => {
+                    (0..rows).for_each(|r| self.clear_cells(r, 0, cols));
+                    self.scrollback.clear();
+                }
+                _ => {}
+            },
+            'K' => match nums.first().copied().unwrap_or(0) {
+                0 => self.clear_cells(self.row, self.col, cols),
+                1 => self.clear_cells(self.row, 0, self.col + 1),
+                2 => self.clear_cells(self.row, 0, cols),
+                _ => {}
+            },
+            // Zeilen einfügen/löschen – nur innerhalb der Scroll-Region
+            'L' if (self.top..=self.bottom).contains(&self.row) => {
+                for _ in 0..arg(0, 1).min(self.bottom - self.row + 1) {
+                    self.grid.remove(self.bottom);
+                    self.grid.insert(self.row, self.blank_line());
+                }
+            }
+            'M' if (self.top..=self.bottom).contains(&self.row) => {
+                for _ in 0..arg(0, 1).min(self.bottom - self.row + 1) {
+                    self.grid.remove(self.row);
+                    self.grid.insert(self.bottom, self.blank_line());
+                }
+            }
+            '@' => {
+                let line = &mut self.grid[self.row];
+                for _ in 0..arg(0, 1).min(cols - self.col) {
+                    line.pop();
+                    line.insert(self.col, Cell::default());
+                }
+            }
+            'P' => {
+                let line = &mut self.grid[self.row];
+                for _ in 0..arg(0, 1).min(cols - self.col) {
+                    line.remove(self.col);
+                    line.push(Cell::default());
+                }
+            }
+            'X' => self.clear_cells(self.row, self.col, self.col.saturating_add(arg(0, 1))),
+            'S' => self.scroll_up(arg(0, 1)),
+            'T' if !private => self.scroll_down(arg(0, 1)),
+            'r' if !private => {
+                let top = arg(0, 1) - 1;
+                let bottom = arg(1, rows).min(rows) - 1;
+                if top < bottom {
+                    self.top = top;
+                    self.bottom = bottom;
+                    self.row = 0;
+                    self.col = 0;
+                }
+            }
+            's' if !private => self.saved_cursor = (self.row, self.col, self.attrs),
+            'u' if !private => self.restore_cursor(),
+            'h' | 'l' if private => {
+                let on = fin == 'h';
+                for &mode in &nums {
+                    match mode {
+                        1 => self.app_cursor = on,
+                        7 => {
+                            self.autowrap = on;
+                            self.wrap_pending = false;
+                        }
+                        25 => self.cursor_visible = on,
+                        1000 => self.set_mouse_mode(MouseMode::Press, on),
+                        1002 => self.set_mouse_mode(MouseMode::Drag, on),
+                        1003 => self.set_mouse_mode(MouseMode::Motion, on),
+                        1006 => self.mouse_sgr = on,
+                        2004 => self.bracketed_paste = on,
+                        47 | 1047 => self.set_alt_screen(on),
+                        1048 if on => self.saved_cursor = (self.row, self.col, self.attrs),
+                        1048 => self.restore_cursor(),
+                        1049 => {
+                            if on {
+                                self.saved_cursor = (self.row, self.col, self.attrs);
+                                self.set_alt_screen(true);
+                            } else {
+                                self.set_alt_screen(false);
+                                self.restore_cursor();
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            'm' if !private => self.sgr(&nums),
+            _ => {}
+        }
+    }
+
+    fn osc_dispatch(&mut self) {
+        let osc = std::mem::take(&mut self.osc);
+        let truncated = std::mem::take(&mut self.osc_truncated);
+        let Some((cmd, arg)) = osc.split_once(';') else {
+            return;
+        };
+        match cmd {
+            // 0 = Icon + Fenster, 2 = Fenster; 1 (nur Icon) ignorieren wir
+            "0" | "2" => self.title = arg.chars().filter(|c| !c.is_control()).collect(),
+            // `52;<Ziele>;<base64>`; `?` (Abfrage) beantworten wir nie – sonst könnte der Remote mitlesen
+            "52" if !truncated => {
+                let Some((_, data)) = arg.split_once(';') else {
+                    return;
+                };
+                if let Ok(bytes) = STANDARD.decode(data.trim()) {
+                    self.clipboard = Some(String::from_utf8_lossy(&bytes).into_owned());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// `l` schaltet nur ab, wenn genau dieser Modus aktiv ist (wie xterm).
+    fn set_mouse_mode(&mut self, mode: MouseMode, on: bool) {
+        if on {
+            self.mouse_mode = mode;
+        } else if self.mouse_mode == mode {
+            self.mouse_mode = MouseMode::Off;
+        }
+    }
+
+    fn sgr(&mut self, nums: &[usize]) {
+        let a = &mut self.attrs;
+        let mut i = 0;
+        while i < nums.len() {
+            let p = nums[i];
+            i += 1;
+            match p {
+                0 => *a = CellAttrs::default(),
+                1 => a.bold = true,
+                3 => a.italic = true,
+                4 => a.underline = true,
+                7 => a.reverse = true,
+                22 => a.bold = false,
+                23 => a.italic = false,
+                24 => a.underline = false,
+                27 => a.reverse = false,
+                30..=37 => a.fg = TermColor::Indexed((p - 30) as u8),
+                90..=97 => a.fg = TermColor::Indexed((p - 90 + 8) as u8),
+                40..=47 => a.bg = TermColor::Indexed((p - 40) as u8),
+                100..=107 => a.bg = TermColor::Indexed((p - 100 + 8) as u8),
+                39 => a.fg = TermColor::Default,
+                49 => a.bg = TermColor::Default,
+                // 38;5;n / 38;2;r;g;b (bzw. 48;…) – Parameter mitverbrauchen
+                38 | 48 => {
+                    let color = match nums.get(i) {
+                        Some(5) => {
+                            let c = nums.get(i + 1).map(|&n| TermColor::Indexed(n as u8));
+                            i += 2;
+                            c
+                        }
+                        Some(2) => {
+                            let c = match (nums.get(i + 1), nums.get(i + 2), nums.get(i + 3)) {
+                                (Some(&r), Some(&g), Some(&b)) => {
+                                    Some(TermColor::Rgb(r as u8, g as u8, b as u8))
+                                }
+                                _ => None,
+                            };
+                            i += 4;
+                            c
+                        }
+                        _ => None,
+                    };
+                    if let Some(c) = color {
+                        if p == 38 {
+                            a.fg = c;
+                        } else {
+                            a.bg = c;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sgr_truecolor() {
+        let mut s = Screen::new(20, 2, 0);
+        s.feed("\x1b[38;2;255;128;0ma\x1b[48;2;1;2;3mb\x1b[0mc");
+        let line = s.lines().next().unwrap();
+        assert_eq!(line[0].attrs.fg, TermColor::Rgb(255, 128, 0));
+        assert_eq!(line[0].attrs.bg, TermColor::Default);
+        assert_eq!(
+            (line[1].attrs.fg, line[1].attrs.bg),
+            (TermColor::Rgb(255, 128, 0), TermColor::Rgb(1, 2, 3))
+        );
+        assert_eq!(line[2].attrs, CellAttrs::default());
+    }
+
+    #[test]
+    fn huge_csi_params_clamp() {
+        let mut s = Screen::new(10, 3, 0);
+        s.feed("\x1b[18446744073709551615C\x1b[99999999999999999999B");
+        assert_eq!(s.cursor(), (2, 9));
+        s.feed("\x1b[1;1Hab\x1b[1;1H\x1b[18446744073709551615X\x1b[18446744073709551615E");
+        assert_eq!(s.text().lines().next(), Some(""));
+        assert_eq!(s.cursor(), (2, 0));
+    }
+}
@@ -0,0 +1,142 @@
+//! Session-Mitschnitt in eine Datei (roh oder zeilenweise mit Zeitstempel).
+//! Geschrieben wird direkt aus dem Reader-Thread – ohne UTF-8-Umweg.
+
+use anyhow::{Context, Result};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Wie oft spätestens auf die Platte geschrieben wird.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogMode {
+    /// Bytes exakt wie empfangen (auch Binär-/Escape-Sequenzen)
+    Raw,
+    /// Jede Zeile mit ISO-8601-Zeitstempel (UTC) davor. `\r`-Überschreibungen
+    /// (Fortschrittsbalken, Spinner) und `\b` werden vorher aufgelöst.
+    Timestamped,
+}
+
+pub(crate) struct SessionLog {
+    out: BufWriter<File>,
+    mode: LogMode,
+    /// Timestamped: aktuelle Zeile + Zeitpunkt ihres ersten Bytes
+    line: Vec<u8>,
+    line_ts: Option<String>,
+    /// `\r` gesehen; folgt kein `\n`, wird die Zeile überschrieben
+    cr: bool,
+    last_flush: Instant,
+}
+
+impl SessionLog {
+    /// Öffnet `path` zum Anhängen.
+    pub(crate) fn open(path: &Path, mode: LogMode) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Log {} nicht schreibbar", path.display()))?;
+        Ok(Self {
+            out: BufWriter::new(file),
+            mode,
+            line: Vec::new(),
+            line_ts: None,
+            cr: false,
+            last_flush: Instant::now(),
+        })
+    }
+
+    pub(crate) fn write(&mut self, data: &[u8]) -> std::io::Result<()> {
+        match self.mode {
+            LogMode::Raw => self.out.write_all(data)?,
+            LogMode::Timestamped => {
+                for &b in data {
+                    match b {
+                        b'\n' => {
+                            self.cr = false;
+                            self.write_line()?;
+                        }
+                        b'\r' => self.cr = true,
+                        0x08 => {
+                            // ganzes UTF-8-Zeichen entfernen
+                            while let Some(c) = self.line.pop() {
+                                if c & 0xC0 != 0x80 {
+                                    break;
+                                }
+                            }
+                        }
+                        _ => {
+                            if std::mem::take(&mut self.cr) {
+                                self.line.clear();
+                            }
+                            self.line_ts.get_or_insert_with(iso8601_now);
+                            self.line.push(b);
+                        }
+                    }
+                }
+            }
+        }
+        self.flush_if_due()
+    }
+
+    /// Timestamped: fertige Zeile mit Zeitstempel schreiben.
+    fn write_line(&mut self) -> std::io::Result<()> {
+        let ts = self.line_ts.take().unwrap_or_else(iso8601_now);
+        write!(self.out, "[{ts}] ")?;
+        self.out.write_all(&self.line)?;
+        self.out.write_all(b"\n")?;
+        self.line.clear();
+        Ok(())
+    }
+
+    pub(crate) fn flush_if_due(&mut self) -> std::io::Result<()> {
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.last_flush = Instant::now();
+            self.out.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for SessionLog {
+    fn drop(&mut self) {
+        // angefangene Zeile (z. B. der Prompt) nicht verlieren
+        if !self.line.is_empty() {
+            let _ = self.write_line();
+        }
+        let _ = self.out.flush();
+    }
+}
+
+/// `YYYY-MM-DDTHH:MM:SS.mmmZ` ohne Zusatz-Crate.
+fn iso8601_now() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let (year, month, day, rem) = civil_from_unix(now.as_secs());
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60,
+        now.subsec_millis()
+    )
+}
+
+/// Unix-Sekunden → (Jahr, Monat, Tag, Sekunden seit Mitternacht), UTC (Tage → Datum nach H. Hinnant).
+pub(crate) fn civil_from_unix(secs: u64) -> (i64, i64, i64, u64) {
+    let (days, rem) = ((secs / 86_400) as i64, secs % 86_400);
+
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day, rem)
+}
@@ -1,11 +1,24 @@
 #![cfg_attr(windows, windows_subsystem = "windows")] // keine extra Konsole
 
 use eframe::egui;
-use egui::{text::LayoutJob, Color32, FontId, Id, TextFormat};
-use starr_core::{StarrProfile, StarrSession};
-use std::sync::mpsc;
+use egui::{
+    text::{LayoutJob, LayoutSection},
+    Color32, FontId, Id, TextFormat,
+};
+use starr_core::term::{
+    line_spans, CellAttrs, Frame, MouseAction, MouseButton, MouseMode, Screen, Span, TermColor,
+};
+use starr_core::{
+    split_host_port, trust_host_key, AuthPrompt, BehaviorSettings, BellMode, ConnectError,
+    ConnectionInfo, ForwardSpec, LogMode, Palette, PromptResponder, ReaderConfig, Rgb,
+    SavedProfile, SessionEvent, SftpEntry, StarrConfig, StarrError, StarrProfile, StarrSession,
+    StarrSftp, Theme, UiSettings, Utf8Stream, WindowGeometry, DEFAULT_FONT_SIZE,
+};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 /* ---------- Worker-IPC ---------- */
 
@@ -13,20 +26,99 @@ use std::time::{Duration, Instant};
 enum ToWorker {
     SendText(String),
     Resize(u32, u32),
+    ForwardLocal(u32, ForwardSpec),
+    CloseForward(u32),
+    StartLog(PathBuf),
+    StopLog,
+    Sftp(SftpCmd),
     Close,
 }
 
+/// Aufträge an den SFTP-Thread eines Tabs (eigener Channel auf derselben Session).
+#[derive(Debug)]
+enum SftpCmd {
+    /// Verzeichnis auflösen (`.` → Home) und anzeigen
+    List(String),
+    Download(PathBuf, PathBuf),
+    /// Dateien/Verzeichnisse in das Verzeichnis hochladen (relativ zum Home)
+    Upload(Vec<PathBuf>, String),
+    Remove(PathBuf),
+    Rename(PathBuf, PathBuf),
+}
+
 #[derive(Debug)]
 enum FromWorker {
-    ConnectedOk,
+    ConnectedOk(ConnectionInfo),
     ConnectedErr(String),
-    Data(String),
+    /// Anmeldung abgelehnt → neu verbinden hilft nicht, Zugangsdaten ändern schon
+    AuthErr(String),
+    UnknownHostKey(ConnectError),
+    AuthPrompt(PendingPrompt),
+    /// Fortschritt beim Verbinden (Handshake, Auth-Methode, …)
+    Progress(SessionEvent),
+    /// Neuer Stand des Grids, vom Worker schon geparst
+    Frame(Frame),
+    /// Der Remote echot Getipptes selbst
+    RemoteEcho,
+    ForwardErr(u32, String),
+    LogErr(String),
+    /// Durchsatz in Bytes/s (↓, ↑), etwa einmal pro Sekunde
+    Rate(f64, f64),
+    /// SFTP: Inhalt des aktuellen Verzeichnisses (absoluter Pfad)
+    SftpListing(PathBuf, Vec<SftpEntry>),
+    SftpErr(String),
+    /// Übertragung läuft: Beschriftung, übertragen, gesamt (Bytes)
+    Transfer(String, u64, u64),
+    /// Übertragung fertig oder abgebrochen; das Ergebnis steht als Zeile im Terminal
+    TransferDone,
     Closed(String),
 }
 
+/// Offene keyboard-interactive-Abfrage; der Worker wartet auf `reply`.
+#[derive(Debug)]
+struct PendingPrompt {
+    instructions: String,
+    prompts: Vec<AuthPrompt>,
+    answers: Vec<String>,
+    reply: mpsc::Sender<Vec<String>>,
+}
+
+/// Leitet Prompts an den UI-Thread weiter und blockiert den Worker bis zur Antwort.
+struct GuiPrompter {
+    tx: mpsc::Sender<FromWorker>,
+}
+
+impl PromptResponder for GuiPrompter {
+    fn respond(
+        &mut self,
+        _username: &str,
+        instructions: &str,
+        prompts: &[AuthPrompt],
+    ) -> Vec<String> {
+        let (reply, answer) = mpsc::channel();
+        let pending = PendingPrompt {
+            instructions: instructions.to_string(),
+            prompts: prompts.to_vec(),
+            answers: vec![String::new(); prompts.len()],
+            reply,
+        };
+        if self.tx.send(FromWorker::AuthPrompt(pending)).is_err() {
+            return vec![String::new(); prompts.len()];
+        }
+        answer
+            .recv()
+            .unwrap_or_else(|_| vec![String::new(); prompts.len()])
+    }
+}
+
 /* ---------- App ---------- */
 
 pub struct App {
+    // Gespeicherte Profile (config.toml), Secrets im Schlüsselbund
+    config: StarrConfig,
+    profile_name: String,
+    remember_secret: bool,
+
     // Connect-Form
     host: String,
     port: u16,
@@ -34,107 +126,676 @@ pub struct App {
     key_path: String,
     passphrase: String,
     password: String,
+    /// Passwortfeld beim nächsten Frame fokussieren (vorausgewähltes Profil ohne Secret)
+    focus_password: bool,
+    use_agent: bool,
+    connect_timeout_secs: u64,
+    handshake_timeout_secs: u64,
+    stderr_red: bool,
+    jump: String,
+    auto_reconnect: bool,
+    compress: bool,
+    env: Vec<(String, String)>,
+    term: String,
+    prefer_ipv6: bool,
+    /// Algorithmen, kommagetrennt (leer → Default)
+    ciphers: String,
+    kex: String,
+    host_key_algos: String,
+    macs: String,
+    /// Startbefehl nach dem Verbinden (leer → keiner)
+    on_connect: String,
+    /// Farbe + Kennung des Profils für Tab und Banner
+    profile_color: Option<Rgb>,
+    profile_label: String,
+    confirm_send: bool,
 
     // State
-    connected: bool,
     connect_error: Option<String>,
+    pending_host_key: Option<ConnectError>,
+    /// „Test“ läuft (Ergebnis kommt über `test_rx`) bzw. ist fertig
+    test_rx: Option<mpsc::Receiver<Result<ConnectionInfo, String>>>,
+    test_result: Option<Result<ConnectionInfo, String>>,
+
+    // Sessions als Tabs; Connect-Card über `show_connect` (oder wenn keine Tabs offen)
+    tabs: Vec<SessionTab>,
+    active: usize,
+    show_connect: bool,
+    next_tab_id: u64,
+    split: Option<SplitView>,
+    /// Zuletzt gesetzter Fenstertitel
+    window_title: String,
+
+    // Einstellungen (⚙), persistiert in config.toml
+    show_settings: bool,
+    settings_error: Option<String>,
+    font_path_edit: String,
+
+    // Input
+    input_buf: String,
+}
+
+/// Zwei Tabs gleichzeitig; `panes` sind Tab-Indizes, das fokussierte ist `App::active`.
+struct SplitView {
+    panes: [usize; 2],
+    /// true = nebeneinander (Trenner senkrecht)
+    vertical: bool,
+    /// Anteil des ersten Panes
+    ratio: f32,
+}
+
+/// Eine Verbindung: eigener Worker, eigener Screen samt Render-Cache.
+struct SessionTab {
+    title: String,
+    host: String,
+
+    // State
+    connected: bool,
+    rate: Option<(f64, f64)>,
+    error: Option<String>,
+    pending_prompt: Option<PendingPrompt>,
     tx: Option<mpsc::Sender<ToWorker>>,
     rx: Option<mpsc::Receiver<FromWorker>>,
+    /// Worker-Thread, zum Einsammeln beim Trennen
+    worker: Option<thread::JoinHandle<()>>,
 
     // Terminal
-    view_buf: String,      // echter Output-Buffer (nur Worker schreibt)
-    display_buf: String,   // Anzeige-Puffer fürs Widget (wir ändern den nur, wenn view_buf sich ändert)
+    screen: Arc<Mutex<Screen>>, // Terminal-Grid + Scrollback, gefüttert vom Worker
+    frame: Frame,               // zuletzt erfasstes Grid (Spans), daraus wird gezeichnet
+    display_buf: String, // Text des Screens fürs Widget (nur zusammen mit ansi_job neu gebaut)
     term_id: Id,
 
     // ANSI-Cache + Drosselung
     ansi_job: LayoutJob,
+    line_cache: LineCache,
     ansi_dirty: bool,
     last_ansi_build: Instant,
 
     // Fokus & Layout
     want_focus: bool,
     autoscroll: bool,
+    /// Zeilen am Fensterrand umbrechen; aus → `NOWRAP_COLS` Spalten, horizontal scrollen
+    wrap: bool,
+    /// Zuletzt per `Resize` gesendete Größe (0 = noch keine)
     last_cols: u32,
     last_rows: u32,
+    /// Feste Größe statt Anpassung ans Fenster (Demos, Apps mit Problemen bei krummen Größen)
+    size_lock: Option<(u32, u32)>,
+    /// Zuletzt gemeldete Zelle bei Mausbewegung (keine doppelten Reports)
+    mouse_cell: Option<(usize, usize)>,
+    /// Angefangene Mausrad-Zeilen (Touchpads liefern Bruchteile)
+    wheel_acc: f32,
+    /// Mehrzeiliges Einfügen, wartet auf Bestätigung
+    pending_paste: Option<String>,
+    /// Eingabezeile: Verlauf (älteste zuerst), Position beim Blättern, hat gerade den Fokus
+    input_history: Vec<String>,
+    history_pos: Option<usize>,
+    input_focused: bool,
+    /// Gespeichertes Profil, mit dem verbunden wurde (für den Verlauf)
+    profile_name: Option<String>,
+    /// Farbe + Kennung aus dem Profil (Tab-Streifen, Banner über dem Terminal)
+    color: Option<Color32>,
+    label: Option<String>,
+    /// Zeilen aus der Eingabezeile erst nach Rückfrage senden; wartende Zeile
+    confirm_send: bool,
+    pending_command: Option<String>,
+    /// Lokales Echo: Server echot selbst (vom Worker erkannt) → lokal nichts mehr anzeigen
+    remote_echo: bool,
+    /// Verbindungsaufbau: aktueller Schritt, zuletzt probierte Auth-Methode, Cipher/MAC
+    status: Option<String>,
+    auth_method: Option<&'static str>,
+    crypto: Option<String>,
+    connected_at: Option<Instant>,
+    /// Banner + Verfahren beider Richtungen, für den Tooltip
+    conn_info: Option<ConnectionInfo>,
+    /// Profil + Auto-Reconnect der letzten Verbindung, für „Neu verbinden“
+    last_connect: Option<(StarrProfile, bool)>,
+    /// Verbindungsversuch aus einem bestehenden Tab heraus: Fehler bleiben im Tab
+    reconnecting: bool,
+    /// Letzte ausgelöste Glocke (Entprellung) und Ende des Aufblitzens
+    last_bell: Option<Instant>,
+    flash_until: Option<Instant>,
 
-    // Input
-    input_buf: String, 
-    local_echo: bool, 
+    // Tunnel (-L): id + Beschreibung, Worker hält die Handles
+    show_tunnels: bool,
+    tunnel_spec: String,
+    tunnels: Vec<(u32, String)>,
+    next_tunnel_id: u32,
+
+    // Suche im Scrollback (Ctrl+F)
+    find: FindState,
+
+    // Mitschnitt (Zeitstempel-Modus), None = aus
+    log_path: Option<PathBuf>,
+
+    // Drag & Drop-Upload: abgelegte Pfade (warten auf das Ziel), Zielverzeichnis
+    pending_upload: Option<Vec<PathBuf>>,
+    upload_dir: String,
+    /// Laufende SFTP-Übertragung (Beschriftung, übertragen, gesamt)
+    transfer: Option<(String, u64, u64)>,
+    sftp: SftpBrowser,
 }
 
-impl Default for App {
-    fn default() -> Self {
+impl SessionTab {
+    fn new(id: u64, title: String, host: String, scrollback: usize) -> Self {
+        let mut screen = Screen::new(120, 34, scrollback);
         Self {
-            host: "localhost".into(),
-            port: 22,
-            user: whoami::username(),
-            key_path: String::new(),
-            passphrase: String::new(),
-            password: String::new(),
+            title,
+            host,
 
             connected: false,
-            connect_error: None,
+            rate: None,
+            error: None,
+            pending_prompt: None,
             tx: None,
             rx: None,
+            worker: None,
 
-            view_buf: String::new(),
+            frame: screen.frame(),
+            screen: Arc::new(Mutex::new(screen)),
             display_buf: String::new(),
-            term_id: Id::new("starr-terminal"),
+            term_id: Id::new(("starr-terminal", id)),
 
             ansi_job: LayoutJob::default(),
+            line_cache: LineCache::default(),
             ansi_dirty: true,
             last_ansi_build: Instant::now(),
 
-            want_focus: false,
+            want_focus: true,
             autoscroll: true,
+            wrap: true,
             last_cols: 0,
             last_rows: 0,
+            size_lock: None,
+            mouse_cell: None,
+            wheel_acc: 0.0,
+            pending_paste: None,
+            input_history: Vec::new(),
+            history_pos: None,
+            input_focused: false,
+            profile_name: None,
+            color: None,
+            label: None,
+            confirm_send: false,
+            pending_command: None,
+            remote_echo: false,
+            status: None,
+            auth_method: None,
+            crypto: None,
+            connected_at: None,
+            conn_info: None,
+            last_connect: None,
+            reconnecting: false,
+            last_bell: None,
+            flash_until: None,
+            show_tunnels: false,
+            tunnel_spec: String::new(),
+            tunnels: Vec::new(),
+            next_tunnel_id: 1,
+            find: FindState::default(),
+            log_path: None,
+            pending_upload: None,
+            upload_dir: ".".into(),
+            transfer: None,
+            sftp: SftpBrowser::default(),
+        }
+    }
+
+    /// Verbindung ist weg (Remote beendet oder getrennt): Zustand zurücksetzen,
+    /// Scrollback bleibt für „Neu verbinden“ stehen.
+    fn reset_connection(&mut self) {
+        self.connected = false;
+        self.rate = None;
+        self.log_path = None;
+        self.tunnels.clear();
+        self.transfer = None;
+        // Neu laden nach dem nächsten Verbinden
+        self.sftp.dir.clear();
+        self.sftp.entries.clear();
+        self.tx = None;
+        self.rx = None;
+        // Vollbild-Apps sind mit der Verbindung weg: Modi zurück, Hauptbildschirm zeigen
+        self.update_screen(|s| s.feed(RESET_MODES));
+    }
+
+    /// Lokale Änderung am Screen (Echo, Leeren, …); das Frame gleich mit erneuern,
+    /// sonst wäre sie erst mit dem nächsten Worker-Output zu sehen.
+    fn update_screen(&mut self, f: impl FnOnce(&mut Screen)) {
+        let mut screen = self.screen.lock().unwrap();
+        f(&mut screen);
+        self.frame = screen.frame();
+        self.ansi_dirty = true;
+    }
+}
+
+impl Default for App {
+    fn default() -> Self {
+        let config = StarrConfig::load().unwrap_or_default();
+        let mut app = Self {
+            profile_name: String::new(),
+            remember_secret: false,
+
+            host: "localhost".into(),
+            port: 22,
+            user: whoami::username(),
+            key_path: String::new(),
+            passphrase: String::new(),
+            password: String::new(),
+            focus_password: false,
+            use_agent: config.security.use_agent,
+            connect_timeout_secs: 10,
+            handshake_timeout_secs: 15,
+            stderr_red: false,
+            jump: String::new(),
+            auto_reconnect: config.behavior.auto_reconnect,
+            compress: false,
+            env: Vec::new(),
+            term: starr_core::DEFAULT_TERM.into(),
+            prefer_ipv6: false,
+            ciphers: String::new(),
+            kex: String::new(),
+            host_key_algos: String::new(),
+            macs: String::new(),
+            on_connect: String::new(),
+            profile_color: None,
+            profile_label: String::new(),
+            confirm_send: false,
+
+            connect_error: None,
+            pending_host_key: None,
+            test_rx: None,
+            test_result: None,
+
+            tabs: Vec::new(),
+            active: 0,
+            show_connect: false,
+            next_tab_id: 1,
+            split: None,
+            window_title: "Starr".into(),
+
+            show_settings: false,
+            settings_error: None,
+            font_path_edit: config
+                .ui
+                .font_path
+                .as_ref()
+                .map(|p| p.display().to_string())
+                .unwrap_or_default(),
+            config,
+
             input_buf: String::new(),
-            local_echo: true,  
+        };
+        // Formular der letzten Verbindung; fehlt nur das Passwort, gleich dorthin
+        if let Some(form) = app.config.last_form.clone() {
+            load_profile_into_form(&mut app, &form);
+            app.focus_password =
+                !form.name.is_empty() && !form.has_secret && app.key_path.is_empty();
         }
+        app
     }
 }
 
+/// Fenstergröße beim ersten Start (bzw. ohne gemerkte Geometrie).
+const DEFAULT_WINDOW_SIZE: [f32; 2] = [980.0, 640.0];
+
 fn main() {
-    let mut native_options = eframe::NativeOptions::default();
-    native_options.viewport = egui::ViewportBuilder::default()
-        .with_inner_size([980.0, 640.0])
-        .with_title("Starr");
+    let app = App::default();
+    let mut viewport = egui::ViewportBuilder::default().with_title("Starr");
+    viewport = match app.config.ui.window {
+        Some(w) => {
+            // Winzige Werte (Konfig von Hand kaputt gemacht) nicht übernehmen
+            let viewport = viewport
+                .with_inner_size(w.size.map(|v| v.max(200.0)))
+                .with_maximized(w.maximized);
+            match w.pos {
+                Some(pos) => viewport.with_position(pos),
+                None => viewport,
+            }
+        }
+        None => viewport.with_inner_size(DEFAULT_WINDOW_SIZE),
+    };
+    let native_options = eframe::NativeOptions {
+        viewport,
+        ..Default::default()
+    };
     eframe::run_native(
         "Starr",
         native_options,
-        Box::new(|_cc| Ok(Box::new(App::default()))),
+        Box::new(move |cc| {
+            // egui-eigenes Ctrl+Plus/Minus skaliert die ganze UI – wir zoomen nur das Terminal
+            cc.egui_ctx.options_mut(|o| o.zoom_with_keyboard = false);
+            let _ = install_font(&cc.egui_ctx, &app.config.ui);
+            Ok(Box::new(app))
+        }),
     )
     .ok();
 }
 
+/// Fenstergröße/-position für den nächsten Start in `config.toml` merken.
+/// Maximiert ist die Größe davor unbekannt → die gemerkte bleibt, nur das Flag kommt dazu.
+fn remember_window(app: &mut App, ctx: &egui::Context) {
+    let (inner, outer, maximized) = ctx.input(|i| {
+        let vp = i.viewport();
+        (vp.inner_rect, vp.outer_rect, vp.maximized.unwrap_or(false))
+    });
+    let previous = app.config.ui.window;
+    let window = if maximized {
+        let base = WindowGeometry {
+            size: DEFAULT_WINDOW_SIZE,
+            pos: None,
+            maximized: true,
+        };
+        Some(WindowGeometry {
+            maximized: true,
+            ..previous.unwrap_or(base)
+        })
+    } else {
+        inner.map(|r| WindowGeometry {
+            size: r.size().into(),
+            pos: outer.map(|o| o.min.into()),
+            maximized: false,
+        })
+    };
+    if window.is_none() || window == previous {
+        return;
+    }
+    app.config.ui.window = window;
+    // Komfort – scheitert das Speichern, startet das Fenster eben in Standardgröße
+    let _ = app.config.save();
+}
+
+/// Eigene Monospace-Schrift aus der Konfig vor die egui-Standardschrift setzen.
+/// Ohne `font_path` gelten wieder die egui-Standardschriften.
+fn install_font(ctx: &egui::Context, ui: &UiSettings) -> Result<(), String> {
+    let mut fonts = egui::FontDefinitions::default();
+    let Some(path) = &ui.font_path else {
+        ctx.set_fonts(fonts);
+        return Ok(());
+    };
+    let bytes = std::fs::read(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    fonts
+        .font_data
+        .insert("starr-mono".into(), egui::FontData::from_owned(bytes));
+    fonts
+        .families
+        .entry(egui::FontFamily::Monospace)
+        .or_default()
+        .insert(0, "starr-mono".into());
+    ctx.set_fonts(fonts);
+    Ok(())
+}
+
+/// Grenzen für den Terminal-Zoom (Ctrl + / Ctrl - / Ctrl 0).
+const MIN_FONT_SIZE: f32 = 8.0;
+const MAX_FONT_SIZE: f32 = 40.0;
+
 /* ---------- GUI ---------- */
 
 impl eframe::App for App {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        ctx.set_visuals(egui::Visuals::dark());
+        ctx.set_theme(match self.config.ui.theme {
+            Theme::Dark => egui::ThemePreference::Dark,
+            Theme::Light => egui::ThemePreference::Light,
+            Theme::System => egui::ThemePreference::System,
+        });
 
         poll_worker(self);
+        if ctx.input(|i| i.viewport().close_requested()) {
+            remember_window(self, ctx);
+        }
+
+        // Fenstertitel vom Remote (OSC 0/2) des aktiven Tabs
+        let title = match self
+            .tabs
+            .get(self.active)
+            .map(|t| t.screen.lock().unwrap().title().trim().to_string())
+        {
+            Some(t) if !t.is_empty() => t,
+            _ => "Starr".to_string(),
+        };
+        if title != self.window_title {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.clone()));
+            self.window_title = title;
+        }
+
+        // Ctrl+Tab / Ctrl+Shift+Tab: Tabs durchschalten (vor der Eingabe, sonst geht Tab an den Remote)
+        if self.tabs.len() > 1 {
+            let (next, prev) = ctx.input_mut(|i| {
+                let prev = i.consume_key(
+                    egui::Modifiers::CTRL | egui::Modifiers::SHIFT,
+                    egui::Key::Tab,
+                );
+                (i.consume_key(egui::Modifiers::CTRL, egui::Key::Tab), prev)
+            });
+            let n = self.tabs.len();
+            if next || prev {
+                let to = if next {
+                    (self.active + 1) % n
+                } else {
+                    (self.active + n - 1) % n
+                };
+                select_tab(self, to);
+            }
+        }
+        let terminal = !self.show_connect && !self.tabs.is_empty();
+
+        // Drag & Drop: aufs Fenster gezogene Dateien per SFTP in den aktiven Tab hochladen
+        let dropped: Vec<PathBuf> = ctx.input(|i| {
+            i.raw
+                .dropped_files
+                .iter()
+                .filter_map(|f| f.path.clone())
+                .collect()
+        });
+        if let Some(tab) = self
+            .tabs
+            .get_mut(self.active)
+            .filter(|t| terminal && t.connected && t.transfer.is_none())
+        {
+            if !dropped.is_empty() {
+                // Offener Datei-Browser → dorthin, wo man gerade ist
+                if tab.sftp.open && !tab.sftp.dir.is_empty() {
+                    tab.upload_dir = tab.sftp.dir.clone();
+                }
+                tab.pending_upload = Some(dropped);
+            }
+        }
 
         // Header
+        let mut disconnect = false;
         egui::TopBottomPanel::top("bar").show(ctx, |ui| {
+            let App {
+                tabs,
+                active,
+                connect_error,
+                show_settings,
+                config,
+                ..
+            } = self;
+            let mut tab = tabs.get_mut(*active).filter(|_| terminal);
             ui.horizontal(|ui| {
                 ui.heading("Starr");
                 ui.separator();
-                ui.label(if self.connected { "Verbunden" } else { "Getrennt" });
+                match tab.as_deref() {
+                    Some(t) if t.connected => {
+                        let details = connection_details(t);
+                        let label = ui.label("Verbunden");
+                        if let Some(tip) = connection_tooltip(t) {
+                            label.on_hover_text(tip);
+                        }
+                        // Nach dem Verbinden kurz sichtbar, danach nur noch als Tooltip
+                        if let (Some(d), Some(at)) = (&details, t.connected_at) {
+                            if let Some(left) = CONNECTED_INFO.checked_sub(at.elapsed()) {
+                                ui.weak(d);
+                                ui.ctx().request_repaint_after(left);
+                            }
+                        }
+                    }
+                    Some(t) if t.rx.is_some() => {
+                        ui.spinner();
+                        ui.label(t.status.as_deref().unwrap_or("Verbinde …"));
+                    }
+                    _ => {
+                        ui.label("Getrennt");
+                    }
+                }
+                // Getrennt und kein Versuch läuft → Ziel zeigen und neu verbinden anbieten
+                if let Some(t) = tab
+                    .as_deref_mut()
+                    .filter(|t| !t.connected && t.rx.is_none())
+                {
+                    if let Some((p, _)) = &t.last_connect {
+                        ui.weak(format!("{}@{}:{}", p.user, p.host, p.port));
+                        if ui.button("Neu verbinden").clicked() {
+                            reconnect_tab(t, idle_timeout(&config.behavior));
+                        }
+                    }
+                }
+                if let Some((true, Some((down, up)))) = tab.as_ref().map(|t| (t.connected, t.rate))
+                {
+                    ui.separator();
+                    ui.weak(format!("↓ {} ↑ {}", fmt_rate(down), fmt_rate(up)));
+                }
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                    ui.toggle_value(&mut self.autoscroll, "Autoscroll");
+                    ui.toggle_value(show_settings, "⚙")
+                        .on_hover_text("Einstellungen");
+                    let Some(tab) = tab else { return };
+                    ui.toggle_value(&mut tab.autoscroll, "Autoscroll");
+                    ui.toggle_value(&mut tab.wrap, "Umbruch").on_hover_text(
+                        "Aus: lange Zeilen nicht umbrechen, sondern horizontal scrollen",
+                    );
+                    if tab.last_cols > 0 {
+                        let lock = if tab.size_lock.is_some() { " 🔒" } else { "" };
+                        let label = format!("{}×{}{lock}", tab.last_cols, tab.last_rows);
+                        ui.menu_button(label, |ui| size_lock_menu(ui, tab))
+                            .response
+                            .on_hover_text("An den Server gemeldete Größe (Spalten × Zeilen)");
+                    }
+                    if ui
+                        .button("Leeren")
+                        .on_hover_text("Bildschirm und Scrollback leeren")
+                        .clicked()
+                    {
+                        clear_terminal(tab);
+                    }
+                    if tab.tx.is_some() {
+                        disconnect = ui
+                            .button("Trennen")
+                            .on_hover_text("Verbindung beenden, Tab bleibt offen")
+                            .clicked();
+                    }
+                    if let Some((label, done, total)) = &tab.transfer {
+                        let frac = if *total > 0 {
+                            *done as f32 / *total as f32
+                        } else {
+                            0.0
+                        };
+                        ui.add(
+                            egui::ProgressBar::new(frac)
+                                .desired_width(180.0)
+                                .text(label.as_str()),
+                        )
+                        .on_hover_text(format!(
+                            "{} von {}",
+                            fmt_bytes(*done),
+                            fmt_bytes(*total)
+                        ));
+                    }
+                    if tab.connected {
+                        if ui.toggle_value(&mut tab.sftp.open, "SFTP").clicked()
+                            && tab.sftp.dir.is_empty()
+                        {
+                            request_listing(tab, ".");
+                        }
+                        ui.toggle_value(&mut tab.show_tunnels, "Tunnel");
+                        let log_btn = ui.selectable_label(tab.log_path.is_some(), "Log");
+                        let log_btn = match &tab.log_path {
+                            Some(p) => log_btn.on_hover_text(p.display().to_string()),
+                            None => log_btn.on_hover_text("Session in Datei mitschneiden …"),
+                        };
+                        if log_btn.clicked() {
+                            toggle_logging(tab);
+                        }
+                    }
                 });
             });
-            if let Some(e) = &self.connect_error {
+            let err = match tabs.get(*active).filter(|_| terminal) {
+                Some(t) => &t.error,
+                None => &*connect_error,
+            };
+            if let Some(e) = err {
                 ui.colored_label(Color32::RED, format!("⚠ {e}"));
             }
         });
 
-        if !self.connected && self.tx.is_none() {
-            connect_card(self, ctx);
-        } else {
+        if disconnect {
+            disconnect_tab(self, self.active);
+        }
+
+        if !self.tabs.is_empty() {
+            tab_strip(self, ctx);
+        }
+
+        if terminal {
             terminal_view(self, ctx);
+        } else {
+            connect_card(self, ctx);
+        }
+
+        if self.show_settings {
+            settings_window(self, ctx);
+        }
+        if self.pending_host_key.is_some() {
+            host_key_dialog(self, ctx);
+        }
+        // Hintergrund-Tabs warten, bis ihre Abfrage dran ist
+        if let Some(tab) = self.tabs.iter_mut().find(|t| t.pending_prompt.is_some()) {
+            auth_prompt_dialog(tab, ctx);
+        }
+        if let Some(tab) = self
+            .tabs
+            .get_mut(self.active)
+            .filter(|t| t.pending_paste.is_some())
+        {
+            paste_confirm_dialog(tab, self.config.behavior.local_echo, ctx);
+        }
+        if let Some(tab) = self
+            .tabs
+            .get_mut(self.active)
+            .filter(|t| t.pending_command.is_some())
+        {
+            command_confirm_dialog(tab, &mut self.config, ctx);
+        }
+        if let Some(tab) = self
+            .tabs
+            .get_mut(self.active)
+            .filter(|t| terminal && t.show_tunnels && t.connected)
+        {
+            tunnels_window(tab, ctx);
+        }
+        if let Some(tab) = self
+            .tabs
+            .get_mut(self.active)
+            .filter(|t| terminal && t.pending_upload.is_some())
+        {
+            upload_dialog(tab, ctx);
+        }
+        if terminal && ctx.input(|i| !i.raw.hovered_files.is_empty()) {
+            let painter = ctx.layer_painter(egui::LayerId::new(
+                egui::Order::Foreground,
+                Id::new("drop-hint"),
+            ));
+            let rect = ctx.screen_rect();
+            painter.rect_filled(rect, 0.0, Color32::from_black_alpha(160));
+            let font = egui::TextStyle::Heading.resolve(&ctx.style());
+            let hint = "Loslassen zum Hochladen (SFTP)";
+            painter.text(
+                rect.center(),
+                egui::Align2::CENTER_CENTER,
+                hint,
+                font,
+                Color32::WHITE,
+            );
         }
 
         // 50 ms → deutlich weniger GPU als 16 ms
@@ -144,8 +805,10 @@ impl eframe::App for App {
 
 impl Drop for App {
     fn drop(&mut self) {
-        if let Some(tx) = &self.tx {
-            let _ = tx.send(ToWorker::Close);
+        for tab in &self.tabs {
+            if let Some(tx) = &tab.tx {
+                let _ = tx.send(ToWorker::Close);
+            }
         }
     }
 }
@@ -159,6 +822,22 @@ fn connect_card(app: &mut App, ctx: &egui::Context) {
             ui.set_min_width(420.0);
             ui.heading("Verbinden");
             ui.separator();
+            profile_bar(app, ui);
+            ui.horizontal(|ui| {
+                let mut colored = app.profile_color.is_some();
+                let tip = "Tab und Banner einfärben, z. B. rot für Produktion";
+                if ui.checkbox(&mut colored, "Farbe").on_hover_text(tip).changed() {
+                    app.profile_color = colored.then_some(Rgb([200, 40, 40]));
+                }
+                if let Some(c) = app.profile_color.as_mut() {
+                    ui.color_edit_button_srgb(&mut c.0);
+                }
+                ui.label("Kennung");
+                ui.add(egui::TextEdit::singleline(&mut app.profile_label).desired_width(100.0).hint_text("PROD"));
+            });
+            ui.checkbox(&mut app.confirm_send, "Befehle vor dem Senden bestätigen")
+                .on_hover_text("Jede Zeile aus der Eingabezeile fragt erst nach; Tastendrücke im Terminal nicht");
+            ui.separator();
             ui.label("Host");
             let host_resp = ui.text_edit_singleline(&mut app.host);
             ui.label("Port");
@@ -166,11 +845,76 @@ fn connect_card(app: &mut App, ctx: &egui::Context) {
             ui.label("Benutzer");
             ui.text_edit_singleline(&mut app.user);
             ui.label("Key (optional)");
-            ui.text_edit_singleline(&mut app.key_path);
+            ui.text_edit_singleline(&mut app.key_path)
+                .on_hover_text("Mehrere Keys mit ; trennen – sie werden der Reihe nach probiert");
             ui.label("Passphrase");
             ui.text_edit_singleline(&mut app.passphrase);
             ui.label("oder Passwort");
-            ui.add(egui::TextEdit::singleline(&mut app.password).password(true));
+            let password = ui.add(egui::TextEdit::singleline(&mut app.password).password(true));
+            if std::mem::take(&mut app.focus_password) {
+                password.request_focus();
+            }
+            ui.checkbox(&mut app.remember_secret, "Passwort/Passphrase im Schlüsselbund merken");
+            ui.checkbox(&mut app.use_agent, "SSH-Agent / Pageant verwenden");
+            ui.label("Jump-Host (optional, user@host:port)");
+            ui.text_edit_singleline(&mut app.jump);
+            ui.label("Startbefehl (optional)");
+            ui.add(egui::TextEdit::singleline(&mut app.on_connect).hint_text("tmux attach"))
+                .on_hover_text("Wird einmal gesendet, sobald die Shell bereit ist");
+            ui.horizontal(|ui| {
+                ui.label("Timeout (s) Connect");
+                ui.add(egui::DragValue::new(&mut app.connect_timeout_secs).range(0..=300));
+                ui.label("Handshake");
+                ui.add(egui::DragValue::new(&mut app.handshake_timeout_secs).range(0..=300));
+            });
+            ui.checkbox(&mut app.stderr_red, "stderr rot darstellen");
+            ui.horizontal(|ui| {
+                ui.label("TERM");
+                egui::ComboBox::from_id_salt("term")
+                    .selected_text(app.term.as_str())
+                    .show_ui(ui, |ui| {
+                        for t in ["xterm-256color", "xterm", "screen", "vt100"] {
+                            ui.selectable_value(&mut app.term, t.to_string(), t);
+                        }
+                    });
+            });
+            ui.collapsing("Umgebungsvariablen", |ui| {
+                let mut remove = None;
+                for (i, (k, v)) in app.env.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(k).desired_width(90.0).hint_text("NAME"));
+                        ui.label("=");
+                        ui.add(egui::TextEdit::singleline(v).desired_width(120.0));
+                        if ui.small_button("✖").clicked() {
+                            remove = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = remove {
+                    app.env.remove(i);
+                }
+                if ui.small_button("+ Variable").clicked() {
+                    app.env.push((String::new(), String::new()));
+                }
+            });
+            ui.collapsing("Algorithmen (erweitert)", |ui| {
+                ui.weak("Kommagetrennt in Präferenzreihenfolge, leer = Standard");
+                egui::Grid::new("algos").num_columns(2).show(ui, |ui| {
+                    for (label, value, hint) in [
+                        ("Kex", &mut app.kex, "curve25519-sha256,ecdh-sha2-nistp256"),
+                        ("Host-Key", &mut app.host_key_algos, "ssh-ed25519,rsa-sha2-512"),
+                        ("Cipher", &mut app.ciphers, "aes256-gcm@openssh.com,aes256-ctr"),
+                        ("MAC", &mut app.macs, "hmac-sha2-256,hmac-sha2-512"),
+                    ] {
+                        ui.label(label);
+                        ui.add(egui::TextEdit::singleline(value).desired_width(260.0).hint_text(hint));
+                        ui.end_row();
+                    }
+                });
+            });
+            ui.checkbox(&mut app.prefer_ipv6, "IPv6 bevorzugen");
+            ui.checkbox(&mut app.compress, "Kompression (langsame Leitungen)");
+            ui.checkbox(&mut app.auto_reconnect, "Bei Verbindungsabbruch neu verbinden");
             ui.add_space(10.0);
 
             let go = ui.button("Verbinden").clicked()
@@ -179,371 +923,3471 @@ fn connect_card(app: &mut App, ctx: &egui::Context) {
             if go {
                 start_worker(app);
             }
+            ui.horizontal(|ui| {
+                if app.test_rx.is_some() {
+                    ui.spinner();
+                    ui.label("Teste …");
+                } else if ui.button("Test").on_hover_text("Nur Handshake + Anmeldung prüfen, ohne Shell").clicked() {
+                    start_test(app);
+                }
+                match &app.test_result {
+                    Some(Ok(info)) => {
+                        let details = algorithm_lines(info).join("\n");
+                        ui.colored_label(Color32::GREEN, "✔ Verbindung OK").on_hover_text(details);
+                    }
+                    Some(Err(e)) => {
+                        ui.colored_label(Color32::RED, format!("✖ {e}"));
+                    }
+                    None => {}
+                }
+            });
+            if !app.tabs.is_empty() && ui.button("Zurück zu den Sessions").clicked() {
+                app.show_connect = false;
+            }
         });
     });
 }
 
-fn terminal_view(app: &mut App, ctx: &egui::Context) {
-    // display_buf aktualisieren, wenn neuer Output kam
-    if app.display_buf != app.view_buf {
-        app.display_buf = app.view_buf.clone();
-        app.ansi_dirty = true;
-    }
-    // ANSI-Layout nur bei Bedarf/throttled neu bauen
-    if app.ansi_dirty && app.last_ansi_build.elapsed() >= Duration::from_millis(50) {
-        app.ansi_job = ansi_to_layout_job(&app.display_buf);
-        app.last_ansi_build = Instant::now();
-        app.ansi_dirty = false;
-    }
-
-    egui::CentralPanel::default()
-        .frame(egui::Frame::default().fill(Color32::from_rgb(10, 10, 14)))
-        .show(ctx, |ui| {
-            // 1) Reines Anzeige-Widget: NICHT interaktiv, damit es nicht gegen den Output puffert
-            let mut text = app.display_buf.as_str();
-            let te = egui::TextEdit::multiline(&mut text)
-                .id(app.term_id)
-                .font(egui::TextStyle::Monospace)
-                .code_editor()
-                .interactive(false)        // <- read-only Anzeige
-                .cursor_at_end(true)
-                .desired_width(f32::INFINITY)
-                .desired_rows(30)
-                .layouter(&mut |ui, _t, _| ui.fonts(|f| f.layout_job(app.ansi_job.clone())))
-                .show(ui);
-
-            // 2) Fokus aufs Terminal, damit globales Keyboard-Capture aktiv ist
-            if app.want_focus {
-                te.response.request_focus();
-                app.want_focus = false;
-            }
-
-            // 3) Tastatur/Paste global abgreifen und an Worker senden
-            handle_input_and_send(app, ctx);
-
-            // 4) Auswahl → Auto-Copy (wie PuTTY)
-            if let Some(cr) = te.cursor_range {
-                if ui.input(|i| i.pointer.any_released()) {
-                    let c = cr.as_ccursor_range();
-                    if c.primary.index != c.secondary.index {
-                        let start = c.primary.index.min(c.secondary.index);
-                        let end = c.primary.index.max(c.secondary.index);
-                        if let Some(slice) = safe_slice(&app.display_buf, start, end) {
-                            copy_to_clipboard(slice);
-                        }
+/// Profil wählen (füllt das Formular), speichern, löschen.
+fn profile_bar(app: &mut App, ui: &mut egui::Ui) {
+    let mut picked = None;
+    ui.horizontal(|ui| {
+        ui.label("Profil");
+        egui::ComboBox::from_id_salt("profile")
+            .selected_text(if app.profile_name.is_empty() {
+                "—"
+            } else {
+                app.profile_name.as_str()
+            })
+            .show_ui(ui, |ui| {
+                for sp in &app.config.profiles {
+                    if ui
+                        .selectable_label(sp.name == app.profile_name, &sp.name)
+                        .clicked()
+                    {
+                        picked = Some(sp.clone());
                     }
                 }
+            });
+        ui.add(
+            egui::TextEdit::singleline(&mut app.profile_name)
+                .desired_width(120.0)
+                .hint_text("Name"),
+        );
+        if ui.button("Speichern").clicked() {
+            save_profile(app);
+        }
+        if ui.button("Import aus SSH-Config").clicked() {
+            import_ssh_config(app);
+        }
+        if ui.button("Löschen").clicked() && app.config.find(&app.profile_name).is_some() {
+            app.config.remove(&app.profile_name);
+            if let Err(e) = app.config.save() {
+                app.connect_error = Some(e.to_string());
             }
-
-            // 5) Rechtsklick / Middle-Click = Paste+Send
-            te.response.context_menu(|ui| {
-               if ui.button("Einfügen & Senden").clicked() {
-    if let Some(txt) = paste_from_clipboard() {
-        let do_echo = app.local_echo;
-        if do_echo { append_local_echo(app, &txt); }
-        if let Some(tx) = app.tx.as_ref().cloned() {
-            let _ = tx.send(ToWorker::SendText(txt));
         }
+    });
+    if let Some(sp) = picked {
+        load_profile_into_form(app, &sp);
     }
-    ui.close_menu();
 }
-                if ui.button("Alles kopieren").clicked() {
-                    copy_to_clipboard(&app.display_buf);
-                    ui.close_menu();
-                }
-                ui.separator();
-                ui.checkbox(&mut app.local_echo, "Lokales Echo");
-            });
-            if te.response.middle_clicked() {
-    if let Some(txt) = paste_from_clipboard() {
-        let do_echo = app.local_echo;
-        if do_echo { append_local_echo(app, &txt); }
-        if let Some(tx) = app.tx.as_ref().cloned() {
-            let _ = tx.send(ToWorker::SendText(txt));
+
+/// Übernimmt Hosts aus ~/.ssh/config; vorhandene Profile gleichen Namens bleiben unangetastet.
+fn import_ssh_config(app: &mut App) {
+    let Some(path) = starr_core::default_ssh_config_path() else {
+        app.connect_error = Some("Home-Verzeichnis nicht gefunden".into());
+        return;
+    };
+    let imported = match starr_core::parse_ssh_config(&path) {
+        Ok(list) => list,
+        Err(e) => {
+            app.connect_error = Some(format!("{e:#}"));
+            return;
+        }
+    };
+    for sp in imported {
+        if app.config.find(&sp.name).is_none() {
+            app.config.profiles.push(sp);
         }
     }
+    if let Err(e) = app.config.save() {
+        app.connect_error = Some(e.to_string());
+    }
 }
 
-            // 6) Ctrl+Shift+C = alles kopieren (Ctrl+C NICHT abfangen!)
-            let (ctrl, shift) = ctx.input(|i| (i.modifiers.ctrl || i.modifiers.command, i.modifiers.shift));
-            if ctrl && shift && ctx.input(|i| i.key_pressed(egui::Key::C)) {
-                copy_to_clipboard(&app.display_buf);
-            }
-
-            // 7) Resize → Worker
-            if let Some(tx) = &app.tx {
-                let rect = te.response.rect;
-                let char_w = ui.fonts(|f| f.glyph_width(&FontId::monospace(15.0), 'W')).max(8.0);
-                let char_h = ui.text_style_height(&egui::TextStyle::Monospace).max(12.0);
-                let cols = ((rect.width() - 8.0) / char_w).max(20.0) as u32;
-                let rows = ((rect.height() - 8.0) / char_h).max(5.0) as u32;
-                if cols != app.last_cols || rows != app.last_rows {
-                    let _ = tx.send(ToWorker::Resize(cols, rows));
-                    app.last_cols = cols;
-                    app.last_rows = rows;
-                }
-            }
-        });
+fn load_profile_into_form(app: &mut App, sp: &SavedProfile) {
+    let p = &sp.profile;
+    app.profile_name = sp.name.clone();
+    app.remember_secret = sp.has_secret;
+    app.host = p.host.clone();
+    app.port = p.port;
+    app.user = p.user.clone();
+    app.key_path = p
+        .key_path
+        .iter()
+        .chain(&p.identities)
+        .map(|k| k.display().to_string())
+        .collect::<Vec<_>>()
+        .join(";");
+    // Secrets erst beim Verbinden aus dem Schlüsselbund
+    app.password.clear();
+    app.passphrase.clear();
+    app.use_agent = p.use_agent;
+    app.connect_timeout_secs = p.connect_timeout.map_or(0, |d| d.as_secs());
+    app.handshake_timeout_secs = p.handshake_timeout.map_or(0, |d| d.as_secs());
+    app.stderr_red = p.split_stderr;
+    app.jump = p.jump_hosts_spec();
+    app.compress = p.compress;
+    app.env = p.env.clone();
+    app.term = p.term.clone();
+    app.prefer_ipv6 = p.prefer_ipv6;
+    app.ciphers = p.ciphers.join(",");
+    app.kex = p.kex.join(",");
+    app.host_key_algos = p.host_key_algos.join(",");
+    app.macs = p.macs.join(",");
+    app.on_connect = p.on_connect.clone().unwrap_or_default();
+    app.profile_color = sp.color;
+    app.profile_label = sp.label.clone().unwrap_or_default();
+    app.confirm_send = sp.confirm_send;
 }
 
-fn handle_input_and_send(app: &mut App, ctx: &egui::Context) {
-    let Some(tx) = app.tx.as_ref().cloned() else { return; };
+fn save_profile(app: &mut App) {
+    let name = app.profile_name.trim().to_string();
+    if name.is_empty() {
+        app.connect_error = Some("Profilname fehlt.".into());
+        return;
+    }
+    let profile = match profile_from_form(app) {
+        Ok(p) => p,
+        Err(e) => {
+            app.connect_error = Some(e);
+            return;
+        }
+    };
+    let secret = if app.key_path.is_empty() {
+        &app.password
+    } else {
+        &app.passphrase
+    };
+    let had_secret = app.config.find(&name).is_some_and(|sp| sp.has_secret);
 
-    // Eingabe-Events einsammeln
-    let mut to_send = String::new();
-    for ev in ctx.input(|i| i.events.clone()) {
-        use egui::Event::*;
-        match ev {
-            Text(t) => {
-                if !t.is_empty() { to_send.push_str(&t); }
-            }
-            Key { key, pressed, modifiers, .. } if pressed => {
-                if let Some(seq) = map_key(key, modifiers) {
-                    to_send.push_str(&seq);
-                }
-            }
-            _ => {}
+    let mut saved = saved_from_form(app, &name, profile);
+    saved.input_history = app
+        .config
+        .find(&name)
+        .map(|sp| sp.input_history.clone())
+        .unwrap_or_default();
+    if app.remember_secret && !secret.is_empty() {
+        match starr_core::save_secret(&name, secret) {
+            Ok(()) => saved.has_secret = true,
+            // Kein Schlüsselbund → Profil trotzdem speichern, beim Verbinden wird gefragt
+            Err(e) => app.connect_error = Some(format!("Secret nicht gespeichert: {e}")),
         }
+    } else if app.remember_secret {
+        saved.has_secret = had_secret;
+    } else if had_secret {
+        let _ = starr_core::delete_secret(&name);
     }
 
-    if to_send.is_empty() { return; }
-
-    // Optional: lokales Echo, damit du Tippen SOFORT siehst
-    if app.local_echo {
-        append_local_echo(app, &to_send);
+    app.config.upsert(saved);
+    if let Err(e) = app.config.save() {
+        app.connect_error = Some(e.to_string());
     }
-
- let _ = tx.send(ToWorker::SendText(to_send));
 }
 
-// Hängt lokal an den View-Buffer + markiert ANSI dirty
-fn append_local_echo(app: &mut App, s: &str) {
-    append_and_limit(&mut app.view_buf, s, 200_000); // 200KB Limit
-    app.ansi_dirty = true;
+/// Profil samt Farbe, Kennung und Bestätigungs-Modus aus dem Formular (ohne Secrets).
+fn saved_from_form(app: &App, name: &str, profile: StarrProfile) -> SavedProfile {
+    let mut saved = SavedProfile::new(name, profile);
+    saved.color = app.profile_color;
+    saved.label = Some(app.profile_label.trim().to_string()).filter(|l| !l.is_empty());
+    saved.confirm_send = app.confirm_send;
+    saved
 }
 
-/* ---------- Worker ---------- */
-
-fn start_worker(app: &mut App) {
-    app.connect_error = None;
+/// Formular der Verbindung für den nächsten Start merken. `has_secret` wie beim
+/// gespeicherten Profil, sonst würde ein späteres „Speichern“ das Secret löschen.
+fn remember_form(app: &mut App, profile: &StarrProfile) {
+    let name = app.profile_name.trim().to_string();
+    let mut form = saved_from_form(app, &name, profile.clone());
+    form.has_secret = app.config.find(&name).is_some_and(|sp| sp.has_secret);
+    app.config.last_form = Some(form);
+    let _ = app.config.save();
+}
 
-    if app.host.trim().is_empty() {
-        app.connect_error = Some("Host darf nicht leer sein.".into());
-        return;
-    }
-    if app.user.trim().is_empty() {
-        app.connect_error = Some("Benutzer darf nicht leer sein.".into());
+/// TOFU-Dialog: unbekannten Host-Key anzeigen und ggf. in known_hosts übernehmen.
+fn host_key_dialog(app: &mut App, ctx: &egui::Context) {
+    let Some(ConnectError::UnknownHostKey {
+        host, fingerprint, ..
+    }) = &app.pending_host_key
+    else {
         return;
-    }
-
-    let profile = StarrProfile {
-        host: app.host.clone(),
-        port: app.port,
-        user: app.user.clone(),
-        key_path: if app.key_path.is_empty() { None } else { Some(app.key_path.clone().into()) },
-        password: if app.password.is_empty() { None } else { Some(app.password.clone()) },
-        key_passphrase: if app.passphrase.is_empty() { None } else { Some(app.passphrase.clone()) },
     };
+    let (host, fingerprint) = (host.clone(), fingerprint.clone());
 
-    let (tx_cmd, rx_cmd) = mpsc::channel::<ToWorker>();
-    let (tx_evt, rx_evt) = mpsc::channel::<FromWorker>();
+    let mut trust = false;
+    let mut cancel = false;
+    egui::Window::new("Unbekannter Host")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            ui.label(format!("Der Host {host} ist noch nicht in known_hosts."));
+            ui.label("Fingerprint:");
+            ui.monospace(&fingerprint);
+            ui.add_space(6.0);
+            ui.horizontal(|ui| {
+                trust = ui.button("Vertrauen & verbinden").clicked();
+                cancel = ui.button("Abbrechen").clicked();
+            });
+        });
 
-    thread::spawn(move || {
-        let sess = match StarrSession::connect(&profile) {
-            Ok(s) => { let _ = tx_evt.send(FromWorker::ConnectedOk); s }
-            Err(e) => { let _ = tx_evt.send(FromWorker::ConnectedErr(e.to_string())); return; }
-        };
+    if trust {
+        if let Some(err) = app.pending_host_key.take() {
+            let res = profile_from_form(app)
+                .and_then(|p| trust_host_key(&p, &err).map_err(|e| format!("known_hosts: {e}")));
+            match res {
+                Ok(()) => start_worker(app),
+                Err(e) => app.connect_error = Some(e),
+            }
+        }
+    } else if cancel {
+        app.pending_host_key = None;
+    }
+}
 
-        let _ = sess.resize(120, 34);
-        let mut last = Instant::now();
+/// keyboard-interactive: ein Feld pro Prompt, verdeckte Prompts maskiert.
+fn auth_prompt_dialog(tab: &mut SessionTab, ctx: &egui::Context) {
+    let Some(pending) = tab.pending_prompt.as_mut() else {
+        return;
+    };
 
-        loop {
-            // Commands
-            while let Ok(cmd) = rx_cmd.try_recv() {
-                match cmd {
-                    ToWorker::SendText(t) => { let _ = sess.send(&t); }
-                    ToWorker::Resize(c, r) => { let _ = sess.resize(c, r); }
-                    ToWorker::Close => { let _ = tx_evt.send(FromWorker::Closed("geschlossen".into())); return; }
+    let mut send = false;
+    let mut cancel = false;
+    egui::Window::new(format!("Anmeldung – {}", tab.title))
+        .id(egui::Id::new("auth-prompt"))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            if !pending.instructions.is_empty() {
+                ui.label(&pending.instructions);
+                ui.separator();
+            }
+            for (prompt, answer) in pending.prompts.iter().zip(pending.answers.iter_mut()) {
+                ui.label(&prompt.text);
+                let resp = ui.add(egui::TextEdit::singleline(answer).password(!prompt.echo));
+                if resp.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    send = true;
                 }
             }
+            ui.add_space(6.0);
+            ui.horizontal(|ui| {
+                send |= ui.button("OK").clicked();
+                cancel = ui.button("Abbrechen").clicked();
+            });
+        });
 
-            // Output poll
-            let data = sess.read_string();
-            if !data.is_empty() {
-                let _ = tx_evt.send(FromWorker::Data(data));
-                last = Instant::now();
+    if send || cancel {
+        if let Some(p) = tab.pending_prompt.take() {
+            let answers = if send {
+                p.answers
             } else {
-                thread::sleep(Duration::from_millis(10));
-                if last.elapsed() > Duration::from_secs(3600) {
-                    let _ = tx_evt.send(FromWorker::Closed("timeout".into()));
-                    return;
-                }
-            }
+                vec![String::new(); p.prompts.len()]
+            };
+            let _ = p.reply.send(answers);
         }
-    });
-
-    app.tx = Some(tx_cmd);
-    app.rx = Some(rx_evt);
-    app.want_focus = true;
+    }
 }
 
-/* ---------- Utils ---------- */
+/// Vorschau für mehrzeiliges Einfügen: erst nach „Senden“ geht es raus.
+fn paste_confirm_dialog(tab: &mut SessionTab, local_echo: bool, ctx: &egui::Context) {
+    let Some(txt) = tab.pending_paste.as_deref() else {
+        return;
+    };
 
-fn poll_worker(app: &mut App) {
-    let mut drop_rx = false;
-    if let Some(rx) = app.rx.as_ref() {
-        loop {
-            match rx.try_recv() {
-                Ok(FromWorker::ConnectedOk) => {
-                    app.connected = true;
-                    app.connect_error = None;
-                    app.view_buf.clear();
-                    app.display_buf.clear();
-                    app.ansi_job = LayoutJob::default();
-                    app.ansi_dirty = true;
-                    app.last_ansi_build = Instant::now();
-                    app.want_focus = true;
-                }
-                Ok(FromWorker::ConnectedErr(e)) => {
-                    app.connected = false;
-                    app.connect_error = Some(e);
-                    app.tx = None;
-                    drop_rx = true;
-                    break;
+    let mut send = false;
+    let mut cancel = false;
+    egui::Window::new("Mehrzeilig einfügen?")
+        .id(egui::Id::new("paste-confirm"))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            ui.label(format!(
+                "{} Zeilen – jede Zeile wird sofort ausgeführt.",
+                txt.lines().count()
+            ));
+            egui::ScrollArea::vertical()
+                .max_height(240.0)
+                .max_width(560.0)
+                .show(ui, |ui| {
+                    ui.add(
+                        egui::Label::new(egui::RichText::new(txt).monospace())
+                            .wrap_mode(egui::TextWrapMode::Extend),
+                    );
+                });
+            ui.add_space(6.0);
+            ui.horizontal(|ui| {
+                send = ui.button("Senden").clicked();
+                cancel = ui.button("Abbrechen").clicked()
+                    || ui.input(|i| i.key_pressed(egui::Key::Escape));
+            });
+        });
+
+    if send {
+        if let Some(txt) = tab.pending_paste.take() {
+            send_paste(tab, &txt, local_echo);
+        }
+    } else if cancel {
+        tab.pending_paste = None;
+    }
+    if send || cancel {
+        tab.want_focus = true;
+    }
+}
+
+/// Bestätigung vor dem Senden einer Zeile (Profile mit „Befehle bestätigen“).
+/// Enter bestätigt bewusst nicht – sonst wäre die Rückfrage nach einem Tastendruck weg.
+fn command_confirm_dialog(tab: &mut SessionTab, config: &mut StarrConfig, ctx: &egui::Context) {
+    let Some(line) = tab.pending_command.as_deref() else {
+        return;
+    };
+
+    let mut send = false;
+    let mut cancel = false;
+    let target = tab.label.as_deref().unwrap_or(&tab.title);
+    egui::Window::new("Befehl senden?")
+        .id(egui::Id::new("command-confirm"))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            ui.label(format!("Wirklich an {target} senden?"));
+            let text = egui::RichText::new(line).monospace().strong();
+            ui.add(egui::Label::new(text).wrap_mode(egui::TextWrapMode::Wrap));
+            ui.add_space(6.0);
+            ui.horizontal(|ui| {
+                send = ui.button("Senden").clicked();
+                cancel = ui.button("Abbrechen").clicked()
+                    || ui.input(|i| i.key_pressed(egui::Key::Escape));
+            });
+        });
+
+    if send {
+        if let Some(line) = tab.pending_command.take() {
+            submit_line(tab, line, config);
+        }
+    } else if cancel {
+        tab.pending_command = None;
+    }
+    if send || cancel {
+        tab.want_focus = true;
+    }
+}
+
+/// Abgelegte Dateien: Zielverzeichnis abfragen, dann lädt der Worker hoch.
+fn upload_dialog(tab: &mut SessionTab, ctx: &egui::Context) {
+    let Some(files) = tab.pending_upload.as_ref() else {
+        return;
+    };
+
+    let mut start = false;
+    let mut cancel = false;
+    egui::Window::new("Hochladen (SFTP)")
+        .id(egui::Id::new("upload-target"))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            for f in files.iter().take(8) {
+                ui.monospace(f.display().to_string());
+            }
+            if files.len() > 8 {
+                ui.weak(format!("… und {} weitere", files.len() - 8));
+            }
+            ui.add_space(6.0);
+            ui.horizontal(|ui| {
+                ui.label("Zielverzeichnis");
+                let dir =
+                    ui.add(egui::TextEdit::singleline(&mut tab.upload_dir).desired_width(280.0));
+                start = dir.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            });
+            ui.weak("Relativ zum Home-Verzeichnis; vorhandene Dateien werden überschrieben.");
+            ui.add_space(6.0);
+            ui.horizontal(|ui| {
+                start |= ui.button("Hochladen").clicked();
+                cancel = ui.button("Abbrechen").clicked()
+                    || ui.input(|i| i.key_pressed(egui::Key::Escape));
+            });
+        });
+
+    if start {
+        if let (Some(files), Some(tx)) = (tab.pending_upload.take(), &tab.tx) {
+            let dir = match tab.upload_dir.trim() {
+                "" => ".".to_string(),
+                d => d.to_string(),
+            };
+            tab.transfer = Some((String::new(), 0, 0));
+            let _ = tx.send(ToWorker::Sftp(SftpCmd::Upload(files, dir)));
+        }
+    } else if cancel {
+        tab.pending_upload = None;
+    }
+    if start || cancel {
+        tab.want_focus = true;
+    }
+}
+
+/// Lokale Port-Forwardings verwalten (`[bind:]port:host:hostport`).
+fn tunnels_window(tab: &mut SessionTab, ctx: &egui::Context) {
+    let mut open = tab.show_tunnels;
+    egui::Window::new("Tunnel (-L)")
+        .open(&mut open)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut tab.tunnel_spec).hint_text("5432:dbhost:5432"),
+                );
+                if ui.button("Öffnen").clicked() {
+                    match tab.tunnel_spec.trim().parse::<ForwardSpec>() {
+                        Ok(spec) => {
+                            if let Some(tx) = &tab.tx {
+                                let id = tab.next_tunnel_id;
+                                tab.next_tunnel_id += 1;
+                                tab.tunnels.push((id, tab.tunnel_spec.trim().to_string()));
+                                let _ = tx.send(ToWorker::ForwardLocal(id, spec));
+                                tab.tunnel_spec.clear();
+                            }
+                        }
+                        Err(e) => tab.error = Some(e.to_string()),
+                    }
+                }
+            });
+            ui.separator();
+            if tab.tunnels.is_empty() {
+                ui.weak("Keine aktiven Tunnel");
+            }
+            let mut close = None;
+            for (id, desc) in &tab.tunnels {
+                ui.horizontal(|ui| {
+                    ui.monospace(desc);
+                    if ui.small_button("✖").clicked() {
+                        close = Some(*id);
+                    }
+                });
+            }
+            if let Some(id) = close {
+                tab.tunnels.retain(|(i, _)| *i != id);
+                if let Some(tx) = &tab.tx {
+                    let _ = tx.send(ToWorker::CloseForward(id));
+                }
+            }
+        });
+    tab.show_tunnels = open;
+}
+
+/// Darstellung, Verhalten, Sicherheit. Änderungen gelten sofort und landen in config.toml.
+fn settings_window(app: &mut App, ctx: &egui::Context) {
+    let mut open = app.show_settings;
+    let mut changed = false;
+    let (mut apply_font, mut autoscroll, mut reconnect, mut agent) = (false, false, false, false);
+    let mut scrollback = false;
+    let cfg = &mut app.config;
+    egui::Window::new("Einstellungen")
+        .open(&mut open)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.strong("Darstellung");
+            egui::Grid::new("settings-ui")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Schriftgröße");
+                    let size =
+                        egui::Slider::new(&mut cfg.ui.font_size, MIN_FONT_SIZE..=MAX_FONT_SIZE)
+                            .step_by(1.0);
+                    changed |= ui.add(size).changed();
+                    ui.end_row();
+                    ui.label("Schriftdatei");
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::TextEdit::singleline(&mut app.font_path_edit)
+                                .hint_text("eingebaut")
+                                .desired_width(200.0),
+                        );
+                        apply_font = ui.button("Übernehmen").clicked();
+                    });
+                    ui.end_row();
+                    ui.label("Theme");
+                    ui.horizontal(|ui| {
+                        changed |= ui
+                            .radio_value(&mut cfg.ui.theme, Theme::Dark, "Dunkel")
+                            .changed();
+                        changed |= ui
+                            .radio_value(&mut cfg.ui.theme, Theme::Light, "Hell")
+                            .changed();
+                        changed |= ui
+                            .radio_value(&mut cfg.ui.theme, Theme::System, "System")
+                            .changed();
+                    });
+                    ui.end_row();
+                    ui.label("Farbschema");
+                    egui::ComboBox::from_id_salt("palette-preset")
+                        .selected_text(preset_name(&cfg.ui.palette))
+                        .show_ui(ui, |ui| {
+                            for (name, preset) in Palette::PRESETS {
+                                let p = preset();
+                                if ui.selectable_label(cfg.ui.palette == p, name).clicked() {
+                                    cfg.ui.palette = p;
+                                    changed = true;
+                                }
+                            }
+                        });
+                    ui.end_row();
+                    ui.label("Vorder-/Hintergrund");
+                    ui.horizontal(|ui| {
+                        changed |= ui
+                            .color_edit_button_srgb(&mut cfg.ui.palette.foreground.0)
+                            .changed();
+                        changed |= ui
+                            .color_edit_button_srgb(&mut cfg.ui.palette.background.0)
+                            .changed();
+                    });
+                    ui.end_row();
+                    ui.label("ANSI 0–7 / 8–15");
+                    ui.vertical(|ui| {
+                        for row in cfg.ui.palette.ansi.chunks_mut(8) {
+                            ui.horizontal(|ui| {
+                                for c in row {
+                                    changed |= ui.color_edit_button_srgb(&mut c.0).changed();
+                                }
+                            });
+                        }
+                    });
+                    ui.end_row();
+                });
+            ui.separator();
+
+            ui.strong("Verhalten");
+            changed |= ui
+                .checkbox(&mut cfg.behavior.local_echo, "Lokales Echo")
+                .on_hover_text(LOCAL_ECHO_HINT)
+                .changed();
+            ui.horizontal(|ui| {
+                ui.label("Glocke");
+                changed |= ui
+                    .radio_value(&mut cfg.behavior.bell, BellMode::Flash, "Blinken")
+                    .changed();
+                changed |= ui
+                    .radio_value(&mut cfg.behavior.bell, BellMode::Sound, "Ton")
+                    .changed();
+                changed |= ui
+                    .radio_value(&mut cfg.behavior.bell, BellMode::Off, "Aus")
+                    .changed();
+            });
+            ui.horizontal(|ui| {
+                ui.label("Scrollback (Zeilen)");
+                let lines = egui::DragValue::new(&mut cfg.behavior.scrollback_lines)
+                    .range(0..=100_000)
+                    .speed(100);
+                scrollback = ui.add(lines).changed();
+            });
+            changed |= ui
+                .checkbox(
+                    &mut cfg.behavior.warn_multiline_paste,
+                    "Bei mehrzeiligem Einfügen nachfragen",
+                )
+                .changed();
+            changed |= ui
+                .checkbox(
+                    &mut cfg.behavior.right_click_paste,
+                    "Rechtsklick fügt ein (PuTTY-Modus)",
+                )
+                .changed();
+            changed |= ui
+                .checkbox(
+                    &mut cfg.behavior.copy_on_select,
+                    "Markieren kopiert (Copy on select)",
+                )
+                .on_hover_text("Aus: Kopieren per Kontextmenü oder Ctrl+Shift+C")
+                .changed();
+            changed |= ui
+                .checkbox(
+                    &mut cfg.behavior.alt_sends_escape,
+                    "Alt sendet Escape (aus für AltGr/Option-Zeichen)",
+                )
+                .changed();
+            autoscroll = ui
+                .checkbox(&mut cfg.behavior.autoscroll, "Autoscroll")
+                .changed();
+            changed |= ui
+                .checkbox(&mut cfg.behavior.wrap_lines, "Lange Zeilen umbrechen")
+                .on_hover_text("Vorgabe für neue Tabs; aus → horizontal scrollen (Tabellen, Logs)")
+                .changed();
+            reconnect = ui
+                .checkbox(
+                    &mut cfg.behavior.auto_reconnect,
+                    "Bei Verbindungsabbruch neu verbinden",
+                )
+                .changed();
+            ui.horizontal(|ui| {
+                ui.label("Nach Minuten ohne Ausgabe trennen");
+                let mins =
+                    egui::DragValue::new(&mut cfg.behavior.idle_timeout_mins).range(0..=24 * 60);
+                changed |= ui
+                    .add(mins)
+                    .on_hover_text("0 = nie. Tote Verbindungen erkennt das Keepalive auch ohne das")
+                    .changed();
+            });
+            changed |= ui
+                .checkbox(
+                    &mut cfg.behavior.input_bar,
+                    "Eingabezeile unter dem Terminal",
+                )
+                .on_hover_text("Zeile tippen, Enter sendet, ↑/↓ holt frühere Eingaben zurück")
+                .changed();
+            ui.add_enabled_ui(cfg.behavior.input_bar, |ui| {
+                changed |= ui
+                    .checkbox(
+                        &mut cfg.behavior.persist_input_history,
+                        "Verlauf im Profil speichern",
+                    )
+                    .changed();
+            });
+            ui.separator();
+
+            ui.strong("Sicherheit");
+            ui.label("known_hosts (leer = ~/.ssh/known_hosts)");
+            let mut kh = cfg
+                .security
+                .known_hosts_path
+                .as_ref()
+                .map(|p| p.display().to_string())
+                .unwrap_or_default();
+            let resp = ui.text_edit_singleline(&mut kh);
+            if resp.changed() {
+                cfg.security.known_hosts_path = (!kh.is_empty()).then(|| PathBuf::from(&kh));
+            }
+            changed |= resp.lost_focus();
+            agent = ui
+                .checkbox(&mut cfg.security.use_agent, "SSH-Agent / Pageant verwenden")
+                .changed();
+            changed |= ui
+                .checkbox(
+                    &mut cfg.security.remote_clipboard,
+                    "Remote darf die Zwischenablage setzen (OSC 52)",
+                )
+                .on_hover_text(
+                    "Yank in vim/tmux landet lokal – aber auch jedes andere Programm dort darf das",
+                )
+                .changed();
+
+            if let Some(e) = &app.settings_error {
+                ui.colored_label(Color32::RED, format!("⚠ {e}"));
+            }
+        });
+    app.show_settings = open;
+
+    if apply_font {
+        let path = app.font_path_edit.trim();
+        app.config.ui.font_path = (!path.is_empty()).then(|| PathBuf::from(path));
+        app.settings_error = install_font(ctx, &app.config.ui).err();
+        changed = true;
+    }
+    // Vorgaben live übernehmen: offene Tabs bzw. das Connect-Formular
+    if autoscroll {
+        for tab in &mut app.tabs {
+            tab.autoscroll = app.config.behavior.autoscroll;
+        }
+    }
+    if scrollback {
+        for tab in &mut app.tabs {
+            tab.screen
+                .lock()
+                .unwrap()
+                .set_max_scrollback(app.config.behavior.scrollback_lines);
+        }
+    }
+    if reconnect {
+        app.auto_reconnect = app.config.behavior.auto_reconnect;
+    }
+    if agent {
+        app.use_agent = app.config.security.use_agent;
+    }
+
+    if changed || autoscroll || scrollback || reconnect || agent {
+        for tab in &mut app.tabs {
+            tab.ansi_dirty = true;
+        }
+        if let Err(e) = app.config.save() {
+            app.settings_error = Some(e.to_string());
+        }
+    }
+}
+
+fn terminal_view(app: &mut App, ctx: &egui::Context) {
+    terminal_zoom(app, ctx);
+    split_keys(app, ctx);
+    let font = term_font(&app.config.ui);
+
+    // Seitenleisten vor dem CentralPanel, sonst liegen sie über dem Terminal
+    if let Some(tab) = app.tabs.get_mut(app.active) {
+        tab.sftp.typing = false;
+        if tab.sftp.open && tab.connected {
+            sftp_panel(tab, ctx);
+        }
+    }
+    if let Some(tab) = app
+        .tabs
+        .get(app.active)
+        .filter(|t| t.color.is_some() || t.label.is_some())
+    {
+        profile_banner(tab, ctx);
+    }
+
+    if app.config.behavior.input_bar {
+        input_bar(app, ctx);
+    } else if let Some(tab) = app.tabs.get_mut(app.active) {
+        tab.input_focused = false;
+    }
+
+    // Ctrl+F → Suchleiste (für das fokussierte Pane)
+    if let Some(tab) = app.tabs.get_mut(app.active) {
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::F)) {
+            tab.find.open = true;
+            tab.find.focus = true;
+        }
+        if tab.find.open {
+            find_bar(tab, ctx);
+        }
+    }
+
+    egui::CentralPanel::default()
+        .frame(egui::Frame::default().fill(rgb(app.config.ui.palette.background)))
+        .show(ctx, |ui| {
+            let App {
+                tabs,
+                active,
+                split,
+                config,
+                ..
+            } = app;
+            let behavior = &mut config.behavior;
+            let palette = &config.ui.palette;
+            let Some(sp) = split.as_mut() else {
+                if let Some(tab) = tabs.get_mut(*active) {
+                    terminal_pane(ui, tab, true, behavior, palette, &font);
+                }
+                return;
+            };
+
+            // Zwei Panes + ziehbarer Trenner
+            let full = ui.available_rect_before_wrap();
+            let (first, divider, second) = split_rects(full, sp.vertical, sp.ratio);
+            let drag = ui.interact(divider, ui.id().with("split-divider"), egui::Sense::drag());
+            if drag.dragged() {
+                let delta = drag.drag_delta();
+                let (d, len) = if sp.vertical {
+                    (delta.x, full.width())
+                } else {
+                    (delta.y, full.height())
+                };
+                sp.ratio = (sp.ratio + d / len.max(1.0)).clamp(0.15, 0.85);
+            }
+            if drag.hovered() || drag.dragged() {
+                ui.ctx().set_cursor_icon(if sp.vertical {
+                    egui::CursorIcon::ResizeHorizontal
+                } else {
+                    egui::CursorIcon::ResizeVertical
+                });
+            }
+            ui.painter()
+                .rect_filled(divider, 0.0, Color32::from_gray(60));
+
+            for (slot, rect) in [first, second].into_iter().enumerate() {
+                let idx = sp.panes[slot];
+                let Some(tab) = tabs.get_mut(idx) else {
+                    continue;
+                };
+                // Klick ins Pane fokussiert es
+                if ui.input(|i| i.pointer.primary_pressed())
+                    && ui.rect_contains_pointer(rect)
+                    && *active != idx
+                {
+                    *active = idx;
+                    tab.want_focus = true;
+                }
+                let focused = *active == idx;
+                ui.allocate_new_ui(egui::UiBuilder::new().max_rect(rect), |ui| {
+                    ui.set_clip_rect(rect);
+                    terminal_pane(ui, tab, focused, behavior, palette, &font);
+                });
+                if focused {
+                    ui.painter().rect_stroke(
+                        rect.shrink(1.0),
+                        0.0,
+                        egui::Stroke::new(1.0, Color32::from_rgb(59, 142, 234)),
+                    );
+                }
+            }
+        });
+}
+
+/// Schmales Banner in der Profilfarbe über dem Terminal, z. B. „PROD · root@db1“.
+fn profile_banner(tab: &SessionTab, ctx: &egui::Context) {
+    let fill = tab.color.unwrap_or(Color32::from_gray(70));
+    // Schrift je nach Helligkeit des Hintergrunds
+    let [r, g, b, _] = fill.to_array();
+    let light = 299 * r as u32 + 587 * g as u32 + 114 * b as u32 > 128_000;
+    let text = if light {
+        Color32::BLACK
+    } else {
+        Color32::WHITE
+    };
+    let target = match &tab.last_connect {
+        Some((p, _)) => format!("{}@{}", p.user, p.host),
+        None => tab.host.clone(),
+    };
+    let caption = match &tab.label {
+        Some(l) => format!("{l} · {target}"),
+        None => target,
+    };
+    egui::TopBottomPanel::top("profile-banner")
+        .frame(
+            egui::Frame::default()
+                .fill(fill)
+                .inner_margin(egui::Margin::symmetric(8.0, 2.0)),
+        )
+        .show(ctx, |ui| {
+            ui.label(egui::RichText::new(caption).color(text).strong());
+        });
+}
+
+/// Ein Terminal in `ui`: Anzeige, Auswahl/Paste, eigene cols/rows → `Resize`.
+/// Tastatur geht nur an das fokussierte Pane.
+fn terminal_pane(
+    ui: &mut egui::Ui,
+    tab: &mut SessionTab,
+    focused: bool,
+    behavior: &mut BehaviorSettings,
+    palette: &Palette,
+    font: &FontId,
+) {
+    // Text + Layout nur bei Bedarf/throttled aus dem Screen neu bauen;
+    // Suchtreffer nur hier (neuer Text) oder bei geänderter Suche neu berechnen
+    if tab.ansi_dirty && tab.last_ansi_build.elapsed() >= LAYOUT_MIN_INTERVAL {
+        let screen = tab.screen.lock().unwrap();
+        (tab.display_buf, tab.ansi_job) =
+            screen_to_layout_job(&tab.frame, &screen, &mut tab.line_cache, palette, font);
+        drop(screen);
+        if tab.find.open && !tab.find.query.is_empty() {
+            tab.find.matches =
+                find_matches(&tab.display_buf, &tab.find.query, tab.find.case_sensitive);
+            tab.find.current = tab
+                .find
+                .current
+                .min(tab.find.matches.len().saturating_sub(1));
+            highlight_matches(&mut tab.ansi_job, &tab.find.matches, tab.find.current);
+        }
+        tab.last_ansi_build = Instant::now();
+        tab.ansi_dirty = false;
+    }
+
+    // Maus an den Remote (tmux, vim, …), sofern angefordert; Shift erzwingt lokale Auswahl
+    let mouse_fwd = focused
+        && tab.tx.is_some()
+        && tab.screen.lock().unwrap().mouse_mode() != MouseMode::Off
+        && !ui.input(|i| i.modifiers.shift);
+    // Alternate Screen hat keinen Scrollback → Mausrad wird zu Pfeiltasten
+    let wheel_keys =
+        focused && !mouse_fwd && tab.tx.is_some() && tab.screen.lock().unwrap().alt_screen();
+
+    // Feste Größe: genau cols×rows oben links, drumherum abgedunkelt (Letterbox)
+    let (char_w, char_h) = ui.fonts(|f| {
+        (
+            f.glyph_width(font, 'W').max(1.0),
+            f.row_height(font).max(1.0),
+        )
+    });
+    let full = ui.available_rect_before_wrap();
+    let view = match tab.size_lock {
+        Some((c, r)) => {
+            let size = egui::vec2(c as f32 * char_w + 8.0, r as f32 * char_h + 8.0);
+            let view = egui::Rect::from_min_size(full.min, size).intersect(full);
+            let shade = Color32::from_black_alpha(120);
+            let right = egui::Rect::from_min_max(egui::pos2(view.max.x, full.min.y), full.max);
+            let below = egui::Rect::from_min_max(
+                egui::pos2(full.min.x, view.max.y),
+                egui::pos2(view.max.x, full.max.y),
+            );
+            ui.painter().rect_filled(right, 0.0, shade);
+            ui.painter().rect_filled(below, 0.0, shade);
+            view
+        }
+        None => full,
+    };
+
+    // 1) Reines Anzeige-Widget: NICHT interaktiv, damit es nicht gegen den Output puffert
+    let jump_to = tab.find.jump.take();
+    let scroll = ui
+        .allocate_new_ui(egui::UiBuilder::new().max_rect(view), |ui| {
+            // Ohne Umbruch ist das Grid breiter als der Bereich → auch horizontal scrollen
+            egui::ScrollArea::new([!tab.wrap, true])
+                .id_salt(tab.term_id)
+                .enable_scrolling(!mouse_fwd && !wheel_keys)
+                .auto_shrink([false, false])
+                .stick_to_bottom(tab.autoscroll)
+                .show(ui, |ui| {
+                    let mut text = tab.display_buf.as_str();
+                    let te = egui::TextEdit::multiline(&mut text)
+                        .id(tab.term_id)
+                        .font(font.clone())
+                        .code_editor()
+                        .interactive(false) // <- read-only Anzeige
+                        .cursor_at_end(true)
+                        // Breite = Grid-Breite, das Layout selbst bricht nie um
+                        .desired_width(if tab.wrap { f32::INFINITY } else { 0.0 })
+                        .desired_rows(30)
+                        .layouter(&mut |ui, _t, _| ui.fonts(|f| f.layout_job(tab.ansi_job.clone())))
+                        .show(ui);
+                    // Suchtreffer in den sichtbaren Bereich holen
+                    if let Some(idx) = jump_to {
+                        let cursor = te.galley.from_ccursor(egui::text::CCursor::new(idx));
+                        let rect = te
+                            .galley
+                            .pos_from_cursor(&cursor)
+                            .translate(te.galley_pos.to_vec2());
+                        ui.scroll_to_rect(rect, Some(egui::Align::Center));
+                    }
+                    te
+                })
+        })
+        .inner;
+    let te = scroll.inner;
+
+    // Visuelle Glocke: Terminal kurz aufhellen
+    if let Some(until) = tab.flash_until {
+        let now = Instant::now();
+        if now < until {
+            ui.painter()
+                .rect_filled(scroll.inner_rect, 0.0, Color32::from_white_alpha(90));
+            ui.ctx().request_repaint_after(until - now);
+        } else {
+            tab.flash_until = None;
+        }
+    }
+
+    // 2) Fokus aufs Terminal, damit globales Keyboard-Capture aktiv ist
+    if focused && tab.want_focus {
+        te.response.request_focus();
+        tab.want_focus = false;
+    }
+
+    // 3) Tastatur/Paste global abgreifen und an Worker senden – nur im fokussierten Pane
+    if focused {
+        handle_input_and_send(tab, behavior, selection_range(&te), ui.ctx());
+    }
+    if mouse_fwd {
+        forward_mouse(ui, tab, te.galley_pos, scroll.inner_rect, font);
+    } else if wheel_keys {
+        wheel_to_arrows(ui, tab, scroll.inner_rect, font);
+    }
+
+    // 4) Auswahl → PRIMARY (Linux) und, falls eingestellt, Auto-Copy (wie PuTTY)
+    let selection = selection_range(&te).filter(|_| !mouse_fwd);
+    let selected = selection.and_then(|(start, end)| safe_slice(&tab.display_buf, start, end));
+    if let Some(slice) = selected.filter(|_| ui.input(|i| i.pointer.any_released())) {
+        copy_to_primary(slice);
+        if behavior.copy_on_select {
+            copy_to_clipboard(slice);
+        }
+    }
+    let selected = selected.map(str::to_owned);
+
+    // 5) Rechtsklick / Middle-Click = Paste+Send (nicht, wenn die Maus an den Remote geht)
+    if !mouse_fwd && behavior.right_click_paste {
+        // PuTTY-Modus: Rechtsklick fügt sofort ein, kein Menü
+        if te.response.secondary_clicked() {
+            paste_and_send(tab, behavior);
+        }
+    } else if !mouse_fwd {
+        te.response.context_menu(|ui| {
+            if ui
+                .add_enabled(selected.is_some(), egui::Button::new("Kopieren"))
+                .clicked()
+            {
+                copy_to_clipboard(selected.as_deref().unwrap_or_default());
+                ui.close_menu();
+            }
+            if ui.button("Einfügen & Senden").clicked() {
+                paste_and_send(tab, behavior);
+                ui.close_menu();
+            }
+            if ui.button("Alles kopieren").clicked() {
+                copy_to_clipboard(&tab.display_buf);
+                ui.close_menu();
+            }
+            if ui.button("Leeren").clicked() {
+                clear_terminal(tab);
+                ui.close_menu();
+            }
+            ui.separator();
+            ui.checkbox(&mut behavior.local_echo, "Lokales Echo")
+                .on_hover_text(LOCAL_ECHO_HINT);
+        });
+    }
+    // Unter Linux wie gewohnt die PRIMARY-Auswahl, sonst die Zwischenablage
+    if !mouse_fwd && te.response.middle_clicked() {
+        if let Some(txt) = paste_from_primary().or_else(paste_from_clipboard) {
+            request_paste(tab, txt, behavior);
+        }
+    }
+
+    // 6) Ctrl+Shift+C = Auswahl kopieren, ohne Auswahl alles (Ctrl+C kopiert nur eine Auswahl, siehe Eingabe)
+    let (ctrl, shift) = ui.input(|i| (i.modifiers.ctrl || i.modifiers.command, i.modifiers.shift));
+    if focused && ctrl && shift && ui.input(|i| i.key_pressed(egui::Key::C)) {
+        copy_to_clipboard(selected.as_deref().unwrap_or(&tab.display_buf));
+    }
+    // Ctrl+L: Bildschirm lokal leeren; `\x0c` geht trotzdem raus, damit die Shell neu zeichnet.
+    // Im Alternate Screen (vim, less) heißt Ctrl+L nur „neu zeichnen“ – dort nichts anfassen.
+    let alt_screen = tab.screen.lock().unwrap().alt_screen();
+    if focused && ctrl && !shift && !alt_screen && ui.input(|i| i.key_pressed(egui::Key::L)) {
+        tab.update_screen(Screen::clear_screen);
+    }
+
+    // 7) Resize → Worker (sichtbarer Bereich, nicht die Texthöhe; bzw. die feste Größe)
+    if let Some(tx) = &tab.tx {
+        let rect = scroll.inner_rect;
+        let fit = ((rect.width() - 8.0) / char_w).max(20.0) as u32;
+        let (cols, rows) = tab.size_lock.unwrap_or((
+            if tab.wrap { fit } else { fit.max(NOWRAP_COLS) },
+            ((rect.height() - 8.0) / char_h).max(5.0) as u32,
+        ));
+        if cols != tab.last_cols || rows != tab.last_rows {
+            // Das Grid passt der Worker an, in Reihenfolge mit dem Output (neues Frame folgt)
+            let _ = tx.send(ToWorker::Resize(cols, rows));
+            tab.last_cols = cols;
+            tab.last_rows = rows;
+        }
+    }
+}
+
+/// Größe festhalten (statt dem Fenster zu folgen) und einstellen.
+fn size_lock_menu(ui: &mut egui::Ui, tab: &mut SessionTab) {
+    let mut locked = tab.size_lock.is_some();
+    if ui.checkbox(&mut locked, "Größe festhalten").changed() {
+        tab.size_lock = locked.then_some((tab.last_cols, tab.last_rows));
+    }
+    let Some((cols, rows)) = &mut tab.size_lock else {
+        return;
+    };
+    ui.horizontal(|ui| {
+        ui.add(egui::DragValue::new(cols).range(20..=500));
+        ui.label("×");
+        ui.add(egui::DragValue::new(rows).range(5..=200));
+    });
+    ui.horizontal(|ui| {
+        for (c, r) in [(80, 24), (120, 34), (132, 43)] {
+            if ui.button(format!("{c}×{r}")).clicked() {
+                (*cols, *rows) = (c, r);
+            }
+        }
+    });
+}
+
+/// Bildschirm und Scrollback leeren; Anzeige-Puffer und Layout-Cache gleich mit,
+/// damit bis zum nächsten Rebuild nichts Altes stehen bleibt.
+fn clear_terminal(tab: &mut SessionTab) {
+    tab.update_screen(|s| {
+        s.clear_scrollback();
+        s.clear_screen();
+    });
+    tab.display_buf.clear();
+    tab.ansi_job = LayoutJob::default();
+    // Prompt neu anfordern
+    if let Some(tx) = &tab.tx {
+        let _ = tx.send(ToWorker::SendText("\x0c".into()));
+    }
+}
+
+/// Pointer-Events über dem Terminal als Maus-Reports an den Remote schicken.
+fn forward_mouse(
+    ui: &egui::Ui,
+    tab: &mut SessionTab,
+    origin: egui::Pos2,
+    view: egui::Rect,
+    font: &FontId,
+) {
+    let Some(tx) = &tab.tx else { return };
+    let screen = tab.screen.lock().unwrap();
+    // Angezeigt wird der Scrollback bis zum Frame, nicht der des Screens (der kann schon weiter sein)
+    let scrollback = tab.line_cache.lines.len();
+    let (char_w, char_h) = ui.fonts(|f| {
+        (
+            f.glyph_width(font, 'W').max(1.0),
+            f.row_height(font).max(1.0),
+        )
+    });
+    // Galley enthält auch den Scrollback → Zeile relativ zum sichtbaren Grid
+    let cell = |pos: egui::Pos2| {
+        let col = ((pos.x - origin.x) / char_w).max(0.0) as usize;
+        let line = ((pos.y - origin.y) / char_h).max(0.0) as usize;
+        (col, line.saturating_sub(scrollback))
+    };
+    let (held, hover) = ui.input(|i| {
+        let held = if i.pointer.primary_down() {
+            MouseButton::Left
+        } else if i.pointer.middle_down() {
+            MouseButton::Middle
+        } else if i.pointer.secondary_down() {
+            MouseButton::Right
+        } else {
+            MouseButton::None
+        };
+        (held, i.pointer.hover_pos().filter(|p| view.contains(*p)))
+    });
+
+    let mut out = String::new();
+    for ev in ui.input(|i| i.events.clone()) {
+        let report = match ev {
+            // Loslassen auch außerhalb melden, sonst hängt die Taste beim Remote
+            egui::Event::PointerButton {
+                pos,
+                button,
+                pressed,
+                ..
+            } if !pressed || view.contains(pos) => {
+                let button = match button {
+                    egui::PointerButton::Primary => MouseButton::Left,
+                    egui::PointerButton::Middle => MouseButton::Middle,
+                    egui::PointerButton::Secondary => MouseButton::Right,
+                    _ => continue,
+                };
+                let action = if pressed {
+                    MouseAction::Press
+                } else {
+                    MouseAction::Release
+                };
+                let (col, row) = cell(pos);
+                screen.mouse_report(button, action, col, row)
+            }
+            egui::Event::PointerMoved(pos) if view.contains(pos) => {
+                let c = cell(pos);
+                if tab.mouse_cell == Some(c) {
+                    continue;
+                }
+                tab.mouse_cell = Some(c);
+                screen.mouse_report(held, MouseAction::Move, c.0, c.1)
+            }
+            egui::Event::MouseWheel { delta, .. } => {
+                let (Some(pos), false) = (hover, delta.y == 0.0) else {
+                    continue;
+                };
+                let button = if delta.y > 0.0 {
+                    MouseButton::WheelUp
+                } else {
+                    MouseButton::WheelDown
+                };
+                let (col, row) = cell(pos);
+                screen.mouse_report(button, MouseAction::Press, col, row)
+            }
+            _ => None,
+        };
+        if let Some(r) = report {
+            out.push_str(&r);
+        }
+    }
+    if !out.is_empty() {
+        let _ = tx.send(ToWorker::SendText(out));
+    }
+}
+
+/// Mausrad im Alternate Screen ohne Maus-Modus → Cursor hoch/runter (less, man, …),
+/// wie PuTTY und xterm. Bruchteile (Touchpad) werden gesammelt.
+fn wheel_to_arrows(ui: &egui::Ui, tab: &mut SessionTab, view: egui::Rect, font: &FontId) {
+    let Some(tx) = &tab.tx else { return };
+    if !ui
+        .input(|i| i.pointer.hover_pos())
+        .is_some_and(|p| view.contains(p))
+    {
+        return;
+    }
+    let char_h = ui.fonts(|f| f.row_height(font)).max(1.0);
+    let rows = tab.screen.lock().unwrap().rows() as f32;
+    for ev in ui.input(|i| i.events.clone()) {
+        if let egui::Event::MouseWheel { unit, delta, .. } = ev {
+            tab.wheel_acc += match unit {
+                egui::MouseWheelUnit::Point => delta.y / char_h,
+                egui::MouseWheelUnit::Line => delta.y,
+                egui::MouseWheelUnit::Page => delta.y * rows,
+            };
+        }
+    }
+    let lines = tab.wheel_acc.trunc();
+    tab.wheel_acc -= lines;
+    if lines == 0.0 {
+        return;
+    }
+    let key = match (lines > 0.0, tab.screen.lock().unwrap().app_cursor_keys()) {
+        (true, false) => "\x1b[A",
+        (false, false) => "\x1b[B",
+        (true, true) => "\x1bOA",
+        (false, true) => "\x1bOB",
+    };
+    let _ = tx.send(ToWorker::SendText(key.repeat(lines.abs() as usize)));
+}
+
+/// Teilt `full` in zwei Panes und den Trenner dazwischen.
+fn split_rects(
+    full: egui::Rect,
+    vertical: bool,
+    ratio: f32,
+) -> (egui::Rect, egui::Rect, egui::Rect) {
+    const DIVIDER: f32 = 6.0;
+    if vertical {
+        let x = full.left() + (full.width() - DIVIDER) * ratio;
+        (
+            egui::Rect::from_min_max(full.min, egui::pos2(x, full.bottom())),
+            egui::Rect::from_min_max(
+                egui::pos2(x, full.top()),
+                egui::pos2(x + DIVIDER, full.bottom()),
+            ),
+            egui::Rect::from_min_max(egui::pos2(x + DIVIDER, full.top()), full.max),
+        )
+    } else {
+        let y = full.top() + (full.height() - DIVIDER) * ratio;
+        (
+            egui::Rect::from_min_max(full.min, egui::pos2(full.right(), y)),
+            egui::Rect::from_min_max(
+                egui::pos2(full.left(), y),
+                egui::pos2(full.right(), y + DIVIDER),
+            ),
+            egui::Rect::from_min_max(egui::pos2(full.left(), y + DIVIDER), full.max),
+        )
+    }
+}
+
+/// Ctrl+Shift+E nebeneinander, Ctrl+Shift+O übereinander; nochmal drücken hebt die Teilung auf.
+fn split_keys(app: &mut App, ctx: &egui::Context) {
+    let mods = egui::Modifiers::CTRL | egui::Modifiers::SHIFT;
+    let (v, h) = ctx.input_mut(|i| {
+        (
+            i.consume_key(mods, egui::Key::E),
+            i.consume_key(mods, egui::Key::O),
+        )
+    });
+    if v || h {
+        toggle_split(app, v);
+    }
+}
+
+fn toggle_split(app: &mut App, vertical: bool) {
+    if app.split.as_ref().is_some_and(|sp| sp.vertical == vertical) {
+        app.split = None;
+        return;
+    }
+    if let Some(sp) = app.split.as_mut() {
+        sp.vertical = vertical;
+        return;
+    }
+    if app.tabs.len() < 2 {
+        if let Some(tab) = app.tabs.get_mut(app.active) {
+            tab.error = Some("Zum Teilen braucht es eine zweite Session (+).".into());
+        }
+        return;
+    }
+    // Zweites Pane: der nächste Tab
+    let other = (app.active + 1) % app.tabs.len();
+    app.split = Some(SplitView {
+        panes: [app.active, other],
+        vertical,
+        ratio: 0.5,
+    });
+}
+
+/// Markierter Bereich als Zeichenindizes in `display_buf`, leere Auswahl = keine.
+/// Jede Grid-Zeile ist dort genau eine Textzeile; eine Block-/Spaltenauswahl setzt hier an.
+fn selection_range(te: &egui::text_edit::TextEditOutput) -> Option<(usize, usize)> {
+    let c = te.cursor_range?.as_ccursor_range();
+    let (start, end) = (
+        c.primary.index.min(c.secondary.index),
+        c.primary.index.max(c.secondary.index),
+    );
+    (start != end).then_some((start, end))
+}
+
+/// `selection`: markierter Bereich (Zeichenindizes) – dann kopiert Ctrl+C, statt zu unterbrechen.
+fn handle_input_and_send(
+    tab: &mut SessionTab,
+    behavior: &BehaviorSettings,
+    selection: Option<(usize, usize)>,
+    ctx: &egui::Context,
+) {
+    let Some(tx) = tab.tx.as_ref().cloned() else {
+        return;
+    };
+    // Tippen gehört gerade der Such- oder Eingabezeile bzw. einem SFTP-Eingabefeld
+    if tab.find.open || tab.input_focused || tab.pending_upload.is_some() || tab.sftp.typing {
+        return;
+    }
+
+    // Eingabe-Events einsammeln
+    let mut to_send = String::new();
+    let (events, mods) = ctx.input(|i| (i.events.clone(), i.modifiers));
+    // Alt+Taste → ESC-Präfix (Meta für Emacs/readline). AltGr kommt als Ctrl+Alt
+    // und bleibt normaler Text.
+    let meta = |m: egui::Modifiers| behavior.alt_sends_escape && m.alt && !m.ctrl;
+    let has_text = events.iter().any(|e| matches!(e, egui::Event::Text(_)));
+    let mut paste = None;
+    for ev in events {
+        use egui::Event::*;
+        match ev {
+            Text(t) if !t.is_empty() => {
+                if meta(mods) {
+                    to_send.push('\x1b');
+                }
+                to_send.push_str(&t);
+            }
+            // Ctrl+C mit Auswahl = Kopieren (wie Windows Terminal), ohne Auswahl → ^C
+            Key {
+                key: egui::Key::C,
+                pressed: true,
+                modifiers,
+                ..
+            } if modifiers.ctrl && !modifiers.shift && selection.is_some() => {
+                let (start, end) = selection.expect("geprüft");
+                if let Some(slice) = safe_slice(&tab.display_buf, start, end) {
+                    copy_to_clipboard(slice);
+                }
+            }
+            Key {
+                key: egui::Key::V,
+                pressed: true,
+                modifiers,
+                ..
+            } if modifiers.ctrl || modifiers.command => {
+                paste = paste_from_clipboard();
+            }
+            // Manche Plattformen liefern zu Alt+Buchstabe keinen Text
+            Key {
+                key,
+                pressed: true,
+                modifiers,
+                ..
+            } if meta(modifiers) && !has_text && key_char(key, modifiers).is_some() => {
+                to_send.push('\x1b');
+                to_send.extend(key_char(key, modifiers));
+            }
+            Key {
+                key,
+                pressed,
+                modifiers,
+                ..
+            } if pressed => {
+                if let Some(seq) =
+                    map_key(key, modifiers, tab.screen.lock().unwrap().app_cursor_keys())
+                {
+                    to_send.push_str(&seq);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Einfügen läuft über die Rückfrage; getippter Text davor geht normal raus
+    if let Some(txt) = paste {
+        if !to_send.is_empty() {
+            if behavior.local_echo {
+                append_local_echo(tab, &to_send);
+            }
+            let _ = tx.send(ToWorker::SendText(std::mem::take(&mut to_send)));
+        }
+        request_paste(tab, txt, behavior);
+    }
+
+    if to_send.is_empty() {
+        return;
+    }
+
+    // Optional: lokales Echo, damit du Tippen SOFORT siehst
+    if behavior.local_echo {
+        append_local_echo(tab, &to_send);
+    }
+
+    let _ = tx.send(ToWorker::SendText(to_send));
+}
+
+fn term_font(ui: &UiSettings) -> FontId {
+    FontId::monospace(ui.font_size)
+}
+
+/// Ctrl + / Ctrl - / Ctrl 0: Schriftgröße ändern. Neue cols/rows ergeben sich im
+/// selben Frame aus der Resize-Berechnung, der Server bekommt also ein passendes `resize`.
+fn terminal_zoom(app: &mut App, ctx: &egui::Context) {
+    use egui::{Key, Modifiers};
+    let size = ctx.input_mut(|i| {
+        let cur = app.config.ui.font_size;
+        if i.consume_key(Modifiers::COMMAND, Key::Plus)
+            || i.consume_key(Modifiers::COMMAND, Key::Equals)
+        {
+            Some(cur + 1.0)
+        } else if i.consume_key(Modifiers::COMMAND, Key::Minus) {
+            Some(cur - 1.0)
+        } else if i.consume_key(Modifiers::COMMAND, Key::Num0) {
+            Some(DEFAULT_FONT_SIZE)
+        } else {
+            None
+        }
+    });
+    let Some(size) = size.map(|s| s.clamp(MIN_FONT_SIZE, MAX_FONT_SIZE)) else {
+        return;
+    };
+    if size == app.config.ui.font_size {
+        return;
+    }
+    app.config.ui.font_size = size;
+    for tab in &mut app.tabs {
+        tab.ansi_dirty = true;
+    }
+    if let (Err(e), Some(tab)) = (app.config.save(), app.tabs.get_mut(app.active)) {
+        tab.error = Some(e.to_string());
+    }
+}
+
+/// Zwischenablage an den Remote schicken (Kontextmenü / Mittelklick).
+fn paste_and_send(tab: &mut SessionTab, behavior: &BehaviorSettings) {
+    if let Some(txt) = paste_from_clipboard() {
+        request_paste(tab, txt, behavior);
+    }
+}
+
+/// Mehrzeiliges erst nach Rückfrage senden – außer der Remote hat Bracketed Paste an,
+/// dann führt die Shell nichts ungefragt aus.
+fn request_paste(tab: &mut SessionTab, txt: String, behavior: &BehaviorSettings) {
+    if behavior.warn_multiline_paste
+        && txt.contains(['\n', '\r'])
+        && !tab.screen.lock().unwrap().bracketed_paste()
+    {
+        tab.pending_paste = Some(txt);
+    } else {
+        send_paste(tab, &txt, behavior.local_echo);
+    }
+}
+
+fn send_paste(tab: &mut SessionTab, txt: &str, local_echo: bool) {
+    if local_echo {
+        append_local_echo(tab, txt);
+    }
+    let Some(tx) = &tab.tx else { return };
+    let out = if tab.screen.lock().unwrap().bracketed_paste() {
+        // Eingebettete Endmarke entfernen, sonst könnte der Text aus dem Rahmen ausbrechen
+        format!("\x1b[200~{}\x1b[201~", txt.replace("\x1b[201~", ""))
+    } else {
+        txt.to_string()
+    };
+    let _ = tx.send(ToWorker::SendText(out));
+}
+
+// Lokal in den Screen + markiert ANSI dirty; entfällt, sobald der Server selbst echot
+fn append_local_echo(tab: &mut SessionTab, s: &str) {
+    if !tab.remote_echo {
+        tab.update_screen(|screen| screen.feed(s));
+    }
+}
+
+const LOCAL_ECHO_HINT: &str =
+    "Zeigt Getipptes sofort an – für Geräte ohne eigenes Echo (Router-Konsolen, \
+Line-Mode). Sobald der Server das Getippte selbst zurückschickt, schaltet sich das lokale Echo \
+für diese Session ab; normale Shells echoen selbst.";
+
+/// Ab so vielen getippten Zeichen zählt ein Treffer als Echo (einzelne sind Zufall).
+const ECHO_PROBE_MIN: usize = 3;
+const ECHO_SEEN_MAX: usize = 256;
+
+/// Taucht das zuletzt Getippte in der Ausgabe auf, echot der Server → lokales Echo aus.
+/// Läuft im Worker: `probe` ist die zuletzt gesendete Zeichenfolge, `seen` das zuletzt Empfangene.
+#[derive(Default)]
+struct EchoDetector {
+    probe: String,
+    seen: String,
+    done: bool,
+}
+
+impl EchoDetector {
+    fn sent(&mut self, s: &str) {
+        if self.done {
+            return;
+        }
+        for c in s.chars() {
+            if c.is_control() {
+                self.probe.clear();
+            } else {
+                self.probe.push(c);
+            }
+        }
+    }
+
+    /// `true` genau einmal: beim ersten erkannten Echo.
+    fn received(&mut self, chunk: &str) -> bool {
+        if self.done {
+            return false;
+        }
+        self.seen.push_str(chunk);
+        if self.seen.len() > ECHO_SEEN_MAX {
+            let mut cut = self.seen.len() - ECHO_SEEN_MAX;
+            while !self.seen.is_char_boundary(cut) {
+                cut += 1;
+            }
+            self.seen.drain(..cut);
+        }
+        if self.probe.chars().count() >= ECHO_PROBE_MIN && self.seen.contains(&self.probe) {
+            self.done = true;
+            self.probe.clear();
+            self.seen.clear();
+        }
+        self.done
+    }
+}
+
+/* ---------- Worker ---------- */
+
+/// So lange sammelt der Worker bei Dauer-Output, bevor er parst und ein `Frame` schickt.
+const COALESCE_WINDOW: Duration = Duration::from_millis(16);
+
+/// Spalten ohne Umbruch (mindestens): breiter als das Fenster, der Rest wird
+/// horizontal gescrollt – wie die Pufferbreite der Windows-Konsole.
+const NOWRAP_COLS: u32 = 250;
+
+/// Mindestabstand zwischen zwei Layout-Neubauten; Frames dazwischen ersetzen nur das
+/// vorige und werden beim nächsten Neubau in einem Rutsch gezeichnet.
+const LAYOUT_MIN_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Versuche für den Auto-Reconnect (1s, 2s, 4s, … Backoff).
+const RECONNECT_ATTEMPTS: u32 = 5;
+
+/// Höchstens so oft geht bei SFTP-Übertragungen ein Fortschritt an die UI.
+const TRANSFER_PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Formular prüfen → Profil samt Secret aus dem Schlüsselbund; Fehler landen in `connect_error`.
+fn form_profile(app: &mut App) -> Option<StarrProfile> {
+    app.connect_error = None;
+
+    if app.host.trim().is_empty() {
+        app.connect_error = Some("Host darf nicht leer sein.".into());
+        return None;
+    }
+    if app.user.trim().is_empty() {
+        app.connect_error = Some("Benutzer darf nicht leer sein.".into());
+        return None;
+    }
+
+    let mut profile = match profile_from_form(app) {
+        Ok(p) => p,
+        Err(e) => {
+            app.connect_error = Some(e);
+            return None;
+        }
+    };
+    if let Some(sp) = app.config.find(app.profile_name.trim()) {
+        sp.fill_secret(&mut profile);
+    }
+    Some(profile)
+}
+
+/// „Test“: Handshake + Anmeldung im Hintergrund, dann trennen – kein Tab, kein Scrollback.
+fn start_test(app: &mut App) {
+    app.test_result = None;
+    let Some(profile) = form_profile(app) else {
+        return;
+    };
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let res = StarrSession::test_connect(&profile).map_err(|e| match e {
+            StarrError::Auth { .. } => format!(
+                "Anmeldung fehlgeschlagen: {}",
+                auth_error_text(&e, &profile)
+            ),
+            e => e.to_string(),
+        });
+        let _ = tx.send(res);
+    });
+    app.test_rx = Some(rx);
+}
+
+fn start_worker(app: &mut App) {
+    app.test_result = None;
+    let Some(profile) = form_profile(app) else {
+        return;
+    };
+    remember_form(app, &profile);
+
+    let title = match app.profile_name.trim() {
+        "" => format!("{}@{}", profile.user, profile.host),
+        name => name.to_string(),
+    };
+    let mut tab = SessionTab::new(
+        app.next_tab_id,
+        title,
+        profile.host.clone(),
+        app.config.behavior.scrollback_lines,
+    );
+    tab.autoscroll = app.config.behavior.autoscroll;
+    tab.wrap = app.config.behavior.wrap_lines;
+    tab.color = app.profile_color.map(rgb);
+    tab.label = Some(app.profile_label.trim().to_string()).filter(|l| !l.is_empty());
+    tab.confirm_send = app.confirm_send;
+    if let Some(sp) = app.config.find(app.profile_name.trim()) {
+        tab.profile_name = Some(sp.name.clone());
+        if app.config.behavior.persist_input_history {
+            tab.input_history = sp.input_history.clone();
+        }
+    }
+    app.next_tab_id += 1;
+    spawn_worker(
+        &mut tab,
+        profile,
+        app.auto_reconnect,
+        idle_timeout(&app.config.behavior),
+    );
+    app.tabs.push(tab);
+    select_tab(app, app.tabs.len() - 1);
+}
+
+/// Verbindet einen getrennten Tab mit dem gemerkten Profil neu; Scrollback bleibt stehen.
+fn reconnect_tab(tab: &mut SessionTab, idle_timeout: Option<Duration>) {
+    let Some((profile, auto_reconnect)) = tab.last_connect.clone() else {
+        return;
+    };
+    tab.error = None;
+    tab.reconnecting = true;
+    tab.update_screen(|s| {
+        s.feed(&format!(
+            "\r\n[Verbinde neu mit {}@{} …]\r\n",
+            profile.user, profile.host
+        ))
+    });
+    spawn_worker(tab, profile, auto_reconnect, idle_timeout);
+}
+
+/// Schließen nach Stille, falls in den Einstellungen gesetzt.
+fn idle_timeout(behavior: &BehaviorSettings) -> Option<Duration> {
+    (behavior.idle_timeout_mins > 0)
+        .then(|| Duration::from_secs(behavior.idle_timeout_mins as u64 * 60))
+}
+
+/// Text in den geteilten Screen parsen (im Worker, nicht im UI-Thread) und den neuen
+/// Stand als fertiges Frame schicken.
+fn feed_frame(screen: &Mutex<Screen>, tx: &mpsc::Sender<FromWorker>, text: &str) {
+    let frame = {
+        let mut screen = screen.lock().unwrap();
+        screen.feed(text);
+        screen.frame()
+    };
+    let _ = tx.send(FromWorker::Frame(frame));
+}
+
+/// SFTP-Thread eines Tabs: ein Channel auf der Session, Aufträge der Reihe nach.
+/// Nach `List` und jeder Änderung geht der Inhalt des aktuellen Verzeichnisses an die UI;
+/// Übertragungen melden Fortschritt und schreiben ihr Ergebnis als Zeile ins Terminal.
+fn spawn_sftp(
+    sess: StarrSession,
+    screen: Arc<Mutex<Screen>>,
+    tx: mpsc::Sender<FromWorker>,
+) -> mpsc::Sender<SftpCmd> {
+    let (tx_cmd, rx_cmd) = mpsc::channel::<SftpCmd>();
+    thread::spawn(move || {
+        let sftp = match sess.sftp() {
+            Ok(s) => s,
+            Err(e) => {
+                let _ = tx.send(FromWorker::SftpErr(format!("{e:#}")));
+                return;
+            }
+        };
+        let mut cwd: Option<PathBuf> = None;
+        for cmd in rx_cmd {
+            let relist = !matches!(cmd, SftpCmd::Download(..));
+            let res = match cmd {
+                SftpCmd::List(path) => sftp.realpath(Path::new(&path)).map(|dir| cwd = Some(dir)),
+                SftpCmd::Download(remote, local) => {
+                    report_transfer(&screen, &tx, download_file(&sftp, &remote, &local, &tx));
+                    Ok(())
+                }
+                SftpCmd::Upload(files, dir) => {
+                    report_transfer(&screen, &tx, upload_files(&sftp, &files, &dir, &tx));
+                    Ok(())
+                }
+                SftpCmd::Remove(path) => sftp.remove(&path),
+                SftpCmd::Rename(from, to) => sftp.rename(&from, &to),
+            };
+            if let Err(e) = res {
+                let _ = tx.send(FromWorker::SftpErr(format!("{e:#}")));
+            }
+            if let Some(dir) = cwd.as_ref().filter(|_| relist) {
+                let msg = match sftp.list(dir) {
+                    Ok(entries) => FromWorker::SftpListing(dir.clone(), entries),
+                    Err(e) => FromWorker::SftpErr(format!("{e:#}")),
+                };
+                let _ = tx.send(msg);
+            }
+        }
+    });
+    tx_cmd
+}
+
+/// Fortschritt an die UI, gedrosselt auf `TRANSFER_PROGRESS_INTERVAL` (das Ende immer).
+fn transfer_progress(tx: &mpsc::Sender<FromWorker>) -> impl FnMut(String, u64, u64) + '_ {
+    let mut last = Instant::now();
+    move |label, done, total| {
+        if last.elapsed() >= TRANSFER_PROGRESS_INTERVAL || done == total {
+            last = Instant::now();
+            let _ = tx.send(FromWorker::Transfer(label, done, total));
+        }
+    }
+}
+
+/// Ergebnis einer Übertragung als Zeile ins Terminal, dann Fortschritt ausblenden.
+fn report_transfer(
+    screen: &Mutex<Screen>,
+    tx: &mpsc::Sender<FromWorker>,
+    res: Result<String, String>,
+) {
+    let line = match res {
+        Ok(msg) => format!("\r\n[{msg}]\r\n"),
+        Err(e) => format!("\r\n[Übertragung fehlgeschlagen: {e}]\r\n"),
+    };
+    feed_frame(screen, tx, &line);
+    let _ = tx.send(FromWorker::TransferDone);
+}
+
+fn upload_files(
+    sftp: &StarrSftp,
+    files: &[PathBuf],
+    dir: &str,
+    tx: &mpsc::Sender<FromWorker>,
+) -> Result<String, String> {
+    let mut progress = transfer_progress(tx);
+    let dir = sftp
+        .realpath(Path::new(dir))
+        .map_err(|e| format!("{e:#}"))?;
+    let bytes = sftp
+        .upload_all(files, &dir, |file, done, total| {
+            let name = file
+                .file_name()
+                .map(|n| n.to_string_lossy())
+                .unwrap_or_default();
+            progress(format!("⬆ {name}"), done, total);
+        })
+        .map_err(|e| format!("{e:#}"))?;
+    Ok(match files {
+        [one] => {
+            let name = one
+                .file_name()
+                .map(|n| n.to_string_lossy())
+                .unwrap_or_default();
+            format!(
+                "Hochgeladen: {name} → {} ({})",
+                dir.display(),
+                fmt_bytes(bytes)
+            )
+        }
+        _ => format!(
+            "Hochgeladen: {} Einträge → {} ({})",
+            files.len(),
+            dir.display(),
+            fmt_bytes(bytes)
+        ),
+    })
+}
+
+fn download_file(
+    sftp: &StarrSftp,
+    remote: &Path,
+    local: &Path,
+    tx: &mpsc::Sender<FromWorker>,
+) -> Result<String, String> {
+    let mut progress = transfer_progress(tx);
+    let name = remote
+        .file_name()
+        .map(|n| n.to_string_lossy())
+        .unwrap_or_default();
+    let bytes = sftp
+        .download_with_progress(remote, local, |done, total| {
+            progress(format!("⬇ {name}"), done, total)
+        })
+        .map_err(|e| format!("{e:#}"))?;
+    Ok(format!(
+        "Heruntergeladen: {name} → {} ({})",
+        local.display(),
+        fmt_bytes(bytes)
+    ))
+}
+
+/// Startet den Session-Thread für `tab`; Befehle/Events laufen über `tab.tx`/`tab.rx`.
+/// Lebendig hält ihn nur die Verbindung selbst (Keepalive im Reader); `idle_timeout`
+/// schließt zusätzlich nach so langer Stille, `None` = nie.
+fn spawn_worker(
+    tab: &mut SessionTab,
+    profile: StarrProfile,
+    auto_reconnect: bool,
+    idle_timeout: Option<Duration>,
+) {
+    tab.last_connect = Some((profile.clone(), auto_reconnect));
+    tab.status = None;
+    tab.auth_method = None;
+    tab.crypto = None;
+    tab.conn_info = None;
+    tab.remote_echo = false;
+    let screen = tab.screen.clone();
+    let (tx_cmd, rx_cmd) = mpsc::channel::<ToWorker>();
+    let (tx_evt, rx_evt) = mpsc::channel::<FromWorker>();
+
+    let worker = thread::spawn(move || {
+        let mut prompter = GuiPrompter { tx: tx_evt.clone() };
+        // Session-Events als Fortschritt weiterreichen (gelesene Bytes interessieren hier nicht)
+        let (tx_sess, rx_sess) = mpsc::channel::<SessionEvent>();
+        let tx_progress = tx_evt.clone();
+        thread::spawn(move || {
+            for ev in rx_sess {
+                if matches!(ev, SessionEvent::BytesRead(_)) {
+                    continue;
+                }
+                if tx_progress.send(FromWorker::Progress(ev)).is_err() {
+                    break;
+                }
+            }
+        });
+        let connected = StarrSession::connect_with_config(
+            &profile,
+            Some(&mut prompter),
+            ReaderConfig::default(),
+            Some(tx_sess),
+        );
+        let mut sess = match connected {
+            Ok(s) => {
+                let _ = tx_evt.send(FromWorker::ConnectedOk(s.connection_info()));
+                s
+            }
+            Err(e) => {
+                let msg = match e {
+                    StarrError::HostKey(ce @ ConnectError::UnknownHostKey { .. }) => {
+                        FromWorker::UnknownHostKey(ce)
+                    }
+                    e @ StarrError::Auth { .. } => {
+                        FromWorker::AuthErr(auth_error_text(&e, &profile))
+                    }
+                    e => FromWorker::ConnectedErr(e.to_string()),
+                };
+                let _ = tx_evt.send(msg);
+                return;
+            }
+        };
+
+        for r in sess.rejected_env() {
+            feed_frame(
+                &screen,
+                &tx_evt,
+                &format!("[Umgebungsvariable abgelehnt: {r}]\r\n"),
+            );
+        }
+
+        let mut size = (120, 34);
+        let _ = sess.resize(size.0, size.1);
+        let mut last = Instant::now();
+        let mut utf8 = Utf8Stream::new();
+        let mut utf8_err = Utf8Stream::new();
+        let mut echo = EchoDetector::default();
+        let mut forwards = Vec::new();
+        let mut sftp: Option<mpsc::Sender<SftpCmd>> = None;
+        // Eingaben, die der Remote noch nicht abgenommen hat (Back-Pressure)
+        let mut outbox: Vec<u8> = Vec::new();
+        let mut last_stats = (Instant::now(), sess.stats());
+
+        loop {
+            // Commands
+            while let Ok(cmd) = rx_cmd.try_recv() {
+                match cmd {
+                    ToWorker::SendText(t) => {
+                        echo.sent(&t);
+                        outbox.extend_from_slice(t.as_bytes());
+                    }
+                    ToWorker::Resize(c, r) => {
+                        size = (c, r);
+                        // Barriere: bis zum window-change Gelesenes noch in der alten Breite
+                        // einspeisen, erst dann das Grid umbauen – sonst bricht es falsch um
+                        let Ok(before) = sess.resize_barrier(c, r) else {
+                            continue;
+                        };
+                        let data = utf8.push(&before);
+                        if echo.received(&data) {
+                            let _ = tx_evt.send(FromWorker::RemoteEcho);
+                        }
+                        let frame = {
+                            let mut screen = screen.lock().unwrap();
+                            screen.feed(&data);
+                            screen.resize(c as usize, r as usize);
+                            screen.frame()
+                        };
+                        let _ = tx_evt.send(FromWorker::Frame(frame));
+                    }
+                    ToWorker::ForwardLocal(id, f) => {
+                        match sess.forward_local(&f.bind_addr, f.bind_port, &f.host, f.host_port) {
+                            Ok(h) => forwards.push((id, h)),
+                            Err(e) => {
+                                let _ = tx_evt.send(FromWorker::ForwardErr(id, e.to_string()));
+                            }
+                        }
+                    }
+                    ToWorker::CloseForward(id) => forwards.retain(|(i, _)| *i != id),
+                    ToWorker::StartLog(path) => {
+                        if let Err(e) = sess.start_logging(&path, LogMode::Timestamped) {
+                            let _ = tx_evt.send(FromWorker::LogErr(format!("{e:#}")));
+                        }
+                    }
+                    ToWorker::StopLog => sess.stop_logging(),
+                    ToWorker::Sftp(cmd) => {
+                        // Eigener Thread: das Terminal bleibt während Übertragungen bedienbar.
+                        // Läuft er nicht (mehr, etwa weil SFTP nicht aufging), neu starten
+                        let cmd = match &sftp {
+                            Some(t) => match t.send(cmd) {
+                                Ok(()) => continue,
+                                Err(mpsc::SendError(cmd)) => cmd,
+                            },
+                            None => cmd,
+                        };
+                        let t = spawn_sftp(sess.weak_clone(), screen.clone(), tx_evt.clone());
+                        let _ = t.send(cmd);
+                        sftp = Some(t);
+                    }
+                    ToWorker::Close => {
+                        let _ = tx_evt.send(FromWorker::Closed("geschlossen".into()));
+                        return;
+                    }
+                }
+            }
+
+            // Ausstehende Eingaben: nur was der Channel gerade nimmt, nie blockieren
+            if !outbox.is_empty() {
+                match sess.try_send(&outbox) {
+                    Ok(n) => {
+                        outbox.drain(..n);
+                    }
+                    Err(_) => outbox.clear(),
+                }
+            }
+
+            // Durchsatz einmal pro Sekunde
+            let elapsed = last_stats.0.elapsed();
+            if elapsed >= Duration::from_secs(1) {
+                let now = sess.stats();
+                let secs = elapsed.as_secs_f64();
+                let down = now.bytes_in.saturating_sub(last_stats.1.bytes_in) as f64 / secs;
+                let up = now.bytes_out.saturating_sub(last_stats.1.bytes_out) as f64 / secs;
+                let _ = tx_evt.send(FromWorker::Rate(down, up));
+                last_stats = (Instant::now(), now);
+            }
+
+            // Remote hat den Channel geschlossen (exit / logout)
+            let closed = sess.is_closed();
+
+            // stderr (nur bei split_stderr befüllt) → rot einfärben
+            let err_bytes = sess.read_stderr_bytes();
+            if !err_bytes.is_empty() {
+                let data = utf8_err.push(&err_bytes);
+                if !data.is_empty() {
+                    feed_frame(&screen, &tx_evt, &format!("\x1b[31m{data}\x1b[0m"));
+                }
+                last = Instant::now();
+            }
+
+            // Output poll; bei Bursts (cat biglog) weiter einsammeln und in einem Rutsch parsen.
+            // Kommt nichts nach (Tippen), geht es nach einer kurzen Pause raus.
+            let mut bytes = sess.read_bytes();
+            if !bytes.is_empty() {
+                let until = Instant::now() + COALESCE_WINDOW;
+                while Instant::now() < until {
+                    thread::sleep(Duration::from_millis(2));
+                    let more = sess.read_bytes();
+                    if more.is_empty() {
+                        break;
+                    }
+                    bytes.extend_from_slice(&more);
+                }
+                let data = utf8.push(&bytes);
+                if !data.is_empty() {
+                    if echo.received(&data) {
+                        let _ = tx_evt.send(FromWorker::RemoteEcho);
+                    }
+                    feed_frame(&screen, &tx_evt, &data);
+                }
+                last = Instant::now();
+            } else if closed && auto_reconnect && sess.exit_status().is_none() {
+                // Kein Exit-Status → Verbindung weggebrochen, nicht ausgeloggt
+                feed_frame(
+                    &screen,
+                    &tx_evt,
+                    "\r\n[Verbindung verloren – verbinde neu …]\r\n",
+                );
+                match StarrSession::connect_with_retry(
+                    sess.profile(),
+                    RECONNECT_ATTEMPTS,
+                    Duration::from_secs(1),
+                ) {
+                    Ok(s) => {
+                        // Tunnel hängen an der alten Session → schließen und melden
+                        for (id, _) in forwards.drain(..) {
+                            let _ = tx_evt.send(FromWorker::ForwardErr(
+                                id,
+                                "nach Reconnect geschlossen".into(),
+                            ));
+                        }
+                        // SFTP-Thread hängt ebenso an der alten Session; beim nächsten Auftrag neu
+                        sftp = None;
+                        sess = s;
+                        outbox.clear();
+                        last_stats = (Instant::now(), sess.stats());
+                        let _ = sess.resize(size.0, size.1);
+                        feed_frame(&screen, &tx_evt, "[verbunden]\r\n");
+                        last = Instant::now();
+                    }
+                    Err(e) => {
+                        let _ = tx_evt
+                            .send(FromWorker::Closed(format!("Reconnect fehlgeschlagen: {e}")));
+                        return;
+                    }
+                }
+            } else if closed {
+                let msg = match sess.exit_status() {
+                    Some(code) => format!("Remote beendet (exit {code})"),
+                    None => "Remote beendet".into(),
+                };
+                let _ = tx_evt.send(FromWorker::Closed(msg));
+                return;
+            } else {
+                thread::sleep(Duration::from_millis(10));
+                if let Some(limit) = idle_timeout.filter(|&l| last.elapsed() > l) {
+                    let msg = format!("{} min ohne Ausgabe – geschlossen", limit.as_secs() / 60);
+                    let _ = tx_evt.send(FromWorker::Closed(msg));
+                    return;
+                }
+            }
+        }
+    });
+
+    tab.tx = Some(tx_cmd);
+    tab.rx = Some(rx_evt);
+    tab.worker = Some(worker);
+}
+
+/// Baut das Verbindungsprofil aus dem Connect-Formular.
+fn profile_from_form(app: &App) -> Result<StarrProfile, String> {
+    let (host, port) = split_host_port(&app.host, app.port)?;
+    // „a.pem; b.pem“ → key_path a.pem, identities [b.pem]
+    let mut keys = app
+        .key_path
+        .split(';')
+        .map(str::trim)
+        .filter(|k| !k.is_empty())
+        .map(PathBuf::from);
+    let mut profile = StarrProfile {
+        host,
+        port,
+        user: app.user.clone(),
+        key_path: keys.next(),
+        identities: keys.collect(),
+        password: if app.password.is_empty() {
+            None
+        } else {
+            Some(app.password.clone())
+        },
+        key_passphrase: if app.passphrase.is_empty() {
+            None
+        } else {
+            Some(app.passphrase.clone())
+        },
+        key_data: None,
+        use_agent: app.use_agent,
+        known_hosts_path: app.config.security.known_hosts_path.clone(),
+        connect_timeout: secs_opt(app.connect_timeout_secs),
+        handshake_timeout: secs_opt(app.handshake_timeout_secs),
+        keepalive_interval: Some(starr_core::DEFAULT_KEEPALIVE_SECS),
+        split_stderr: app.stderr_red,
+        compress: app.compress,
+        env: app
+            .env
+            .iter()
+            .filter(|(k, _)| !k.trim().is_empty())
+            .map(|(k, v)| (k.trim().to_string(), v.clone()))
+            .collect(),
+        term: app.term.clone(),
+        prefer_ipv6: app.prefer_ipv6,
+        jump: None,
+        ciphers: algo_list(&app.ciphers),
+        kex: algo_list(&app.kex),
+        host_key_algos: algo_list(&app.host_key_algos),
+        macs: algo_list(&app.macs),
+        on_connect: Some(app.on_connect.trim().to_string()).filter(|c| !c.is_empty()),
+        no_shell: false,
+    };
+    if !app.jump.trim().is_empty() {
+        profile
+            .set_jump_hosts(&app.jump)
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(profile)
+}
+
+/* ---------- Utils ---------- */
+
+/// „a, b,,c“ → ["a", "b", "c"]
+fn algo_list(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|a| !a.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Suchleiste: Query, Treffer (Zeichenbereiche in `display_buf`), aktueller Treffer.
+#[derive(Default)]
+struct FindState {
+    open: bool,
+    focus: bool,
+    query: String,
+    case_sensitive: bool,
+    matches: Vec<std::ops::Range<usize>>,
+    current: usize,
+    /// Zeichenindex, zu dem im nächsten Frame gescrollt wird
+    jump: Option<usize>,
+}
+
+/// SFTP-Seitenleiste: aktuelles Verzeichnis (absolut, leer = noch nicht geladen), Inhalt,
+/// Sortierung und die Rückfrage vor Löschen/Umbenennen.
+#[derive(Default)]
+struct SftpBrowser {
+    open: bool,
+    dir: String,
+    entries: Vec<SftpEntry>,
+    loading: bool,
+    sort: SftpSort,
+    descending: bool,
+    /// Lokaler Zielordner für Downloads (leer → beim Öffnen `~/Downloads`)
+    local_dir: String,
+    /// Lokaler Pfad für „Hochladen“
+    upload_path: String,
+    confirm: Option<SftpConfirm>,
+    /// Ein Eingabefeld der Leiste hat den Fokus → Tastatur nicht ans Terminal
+    typing: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+enum SftpSort {
+    #[default]
+    Name,
+    Size,
+    Perm,
+    Mtime,
+}
+
+enum SftpConfirm {
+    Delete(String),
+    /// Alter Name, neuer Name (wird bearbeitet)
+    Rename(String, String),
+}
+
+impl SftpBrowser {
+    /// Verzeichnisse immer oben, darin nach der gewählten Spalte.
+    fn sort_entries(&mut self) {
+        let (key, descending) = (self.sort, self.descending);
+        self.entries.sort_by(|a, b| {
+            let ord = match key {
+                SftpSort::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+                SftpSort::Size => a.size.cmp(&b.size),
+                SftpSort::Perm => a.perm.cmp(&b.perm),
+                SftpSort::Mtime => a.mtime.cmp(&b.mtime),
+            };
+            b.is_dir
+                .cmp(&a.is_dir)
+                .then(if descending { ord.reverse() } else { ord })
+        });
+    }
+
+    /// Remote-Pfad eines Eintrags im aktuellen Verzeichnis (immer mit `/`).
+    fn child(&self, name: &str) -> String {
+        format!("{}/{name}", self.dir.trim_end_matches('/'))
+    }
+}
+
+fn request_listing(tab: &mut SessionTab, dir: &str) {
+    if let Some(tx) = &tab.tx {
+        let _ = tx.send(ToWorker::Sftp(SftpCmd::List(dir.to_string())));
+        tab.sftp.loading = true;
+    }
+}
+
+/// `~/Downloads`, falls vorhanden, sonst das Home-Verzeichnis.
+fn default_download_dir() -> String {
+    let home = std::env::var_os("USERPROFILE")
+        .or_else(|| std::env::var_os("HOME"))
+        .map(PathBuf::from);
+    let home = home.unwrap_or_default();
+    let downloads = home.join("Downloads");
+    (if downloads.is_dir() { downloads } else { home })
+        .display()
+        .to_string()
+}
+
+/// Datei-Browser des aktiven Tabs (rechts): Pfadleiste, sortierbare Liste, Doppelklick öffnet
+/// bzw. lädt herunter, Kontextmenü für Umbenennen/Löschen (mit Rückfrage), Hochladen.
+fn sftp_panel(tab: &mut SessionTab, ctx: &egui::Context) {
+    let b = &mut tab.sftp;
+    if b.local_dir.is_empty() {
+        b.local_dir = default_download_dir();
+    }
+    let mut cmds = Vec::new();
+    let mut go: Option<String> = None;
+    let mut confirm = None;
+    let mut typing = false;
+
+    egui::SidePanel::right("sftp")
+        .resizable(true)
+        .default_width(440.0)
+        .show(ctx, |ui| {
+            // Pfadleiste: jede Ebene anklickbar
+            ui.horizontal_wrapped(|ui| {
+                if ui.small_button("⟳").on_hover_text("Neu laden").clicked() {
+                    go = Some(if b.dir.is_empty() {
+                        ".".into()
+                    } else {
+                        b.dir.clone()
+                    });
+                }
+                if ui
+                    .small_button("⬆")
+                    .on_hover_text("Übergeordnetes Verzeichnis")
+                    .clicked()
+                {
+                    go = b
+                        .dir
+                        .rsplit_once('/')
+                        .map(|(parent, _)| (if parent.is_empty() { "/" } else { parent }).into());
+                }
+                if b.loading {
+                    ui.spinner();
+                }
+                ui.separator();
+                if ui.link("/").clicked() {
+                    go = Some("/".into());
+                }
+                let mut path = String::new();
+                for part in b.dir.split('/').filter(|p| !p.is_empty()) {
+                    path = format!("{path}/{part}");
+                    if ui.link(part).clicked() {
+                        go = Some(path.clone());
+                    }
+                    ui.weak("/");
+                }
+            });
+            ui.separator();
+
+            // Rückfrage vor Löschen / neuer Name
+            let mut done = false;
+            match &mut b.confirm {
+                Some(SftpConfirm::Delete(name)) => {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("„{name}“ löschen?"));
+                        if ui.button("Löschen").clicked() {
+                            cmds.push(SftpCmd::Remove(PathBuf::from(format!(
+                                "{}/{name}",
+                                b.dir.trim_end_matches('/')
+                            ))));
+                            done = true;
+                        }
+                        done |= ui.button("Abbrechen").clicked();
+                    });
+                    ui.weak("Verzeichnisse nur, wenn sie leer sind.");
+                }
+                Some(SftpConfirm::Rename(old, new)) => {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("„{old}“ →"));
+                        let edit = ui.add(egui::TextEdit::singleline(new).desired_width(200.0));
+                        typing |= edit.has_focus();
+                        let enter =
+                            edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                        if (ui.button("Umbenennen").clicked() || enter)
+                            && !new.trim().is_empty()
+                            && new != old
+                        {
+                            let dir = b.dir.trim_end_matches('/');
+                            let (from, to) =
+                                (format!("{dir}/{old}"), format!("{dir}/{}", new.trim()));
+                            cmds.push(SftpCmd::Rename(PathBuf::from(from), PathBuf::from(to)));
+                            done = true;
+                        }
+                        done |= ui.button("Abbrechen").clicked();
+                    });
+                }
+                None => {}
+            }
+            if done {
+                b.confirm = None;
+            }
+
+            // Hochladen (Drag & Drop aufs Fenster geht auch) und Zielordner für Downloads
+            egui::Grid::new("sftp-local").num_columns(2).show(ui, |ui| {
+                ui.label("Hochladen");
+                ui.horizontal(|ui| {
+                    let edit = egui::TextEdit::singleline(&mut b.upload_path)
+                        .hint_text("lokale Datei oder Ordner");
+                    typing |= ui.add(edit.desired_width(240.0)).has_focus();
+                    let path = b.upload_path.trim();
+                    if ui
+                        .add_enabled(!path.is_empty(), egui::Button::new("⬆"))
+                        .clicked()
+                    {
+                        cmds.push(SftpCmd::Upload(vec![PathBuf::from(path)], b.dir.clone()));
+                        b.upload_path.clear();
+                    }
+                });
+                ui.end_row();
+                ui.label("Downloads nach");
+                typing |= ui
+                    .add(egui::TextEdit::singleline(&mut b.local_dir).desired_width(270.0))
+                    .has_focus();
+                ui.end_row();
+            });
+            ui.separator();
+
+            egui::ScrollArea::vertical()
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    egui::Grid::new("sftp-list")
+                        .num_columns(4)
+                        .striped(true)
+                        .spacing([12.0, 2.0])
+                        .show(ui, |ui| {
+                            let columns = [
+                                (SftpSort::Name, "Name"),
+                                (SftpSort::Size, "Größe"),
+                                (SftpSort::Perm, "Rechte"),
+                                (SftpSort::Mtime, "Geändert (UTC)"),
+                            ];
+                            for (key, label) in columns {
+                                let arrow = match (b.sort == key, b.descending) {
+                                    (true, false) => " ▲",
+                                    (true, true) => " ▼",
+                                    (false, _) => "",
+                                };
+                                if ui
+                                    .selectable_label(b.sort == key, format!("{label}{arrow}"))
+                                    .clicked()
+                                {
+                                    b.descending = b.sort == key && !b.descending;
+                                    b.sort = key;
+                                    b.sort_entries();
+                                }
+                            }
+                            ui.end_row();
+
+                            for e in &b.entries {
+                                let icon = if e.is_dir { "📁" } else { "📄" };
+                                let name = ui.add(
+                                    egui::Label::new(format!("{icon} {}", e.name))
+                                        .sense(egui::Sense::click()),
+                                );
+                                let remote = b.child(&e.name);
+                                let local = Path::new(b.local_dir.trim()).join(&e.name);
+                                let download =
+                                    || SftpCmd::Download(PathBuf::from(&remote), local.clone());
+                                if name.double_clicked() {
+                                    if e.is_dir {
+                                        go = Some(remote.clone());
+                                    } else {
+                                        cmds.push(download());
+                                    }
+                                }
+                                name.context_menu(|ui| {
+                                    if !e.is_dir && ui.button("Herunterladen").clicked() {
+                                        cmds.push(download());
+                                        ui.close_menu();
+                                    }
+                                    if ui.button("Umbenennen …").clicked() {
+                                        confirm = Some(SftpConfirm::Rename(
+                                            e.name.clone(),
+                                            e.name.clone(),
+                                        ));
+                                        ui.close_menu();
+                                    }
+                                    if ui.button("Löschen …").clicked() {
+                                        confirm = Some(SftpConfirm::Delete(e.name.clone()));
+                                        ui.close_menu();
+                                    }
+                                });
+                                ui.label(if e.is_dir {
+                                    String::new()
+                                } else {
+                                    e.size.map(fmt_bytes).unwrap_or_default()
+                                });
+                                ui.monospace(e.perm_string());
+                                ui.label(e.mtime_string().unwrap_or_default());
+                                ui.end_row();
+                            }
+                        });
+                });
+        });
+
+    tab.sftp.typing = typing;
+    if confirm.is_some() {
+        tab.sftp.confirm = confirm;
+    }
+    if let Some(dir) = go {
+        request_listing(tab, &dir);
+    }
+    let Some(tx) = &tab.tx else { return };
+    for cmd in cmds {
+        if matches!(cmd, SftpCmd::Upload(..) | SftpCmd::Download(..)) {
+            tab.transfer = Some((String::new(), 0, 0));
+        }
+        let _ = tx.send(ToWorker::Sftp(cmd));
+    }
+}
+
+/// So viele Zeilen merkt sich die Eingabezeile je Tab bzw. Profil.
+const INPUT_HISTORY_MAX: usize = 200;
+
+/// Eingabezeile unter dem Terminal (aktiver Tab): Enter sendet `zeile\r`, ↑/↓ blättert im Verlauf.
+fn input_bar(app: &mut App, ctx: &egui::Context) {
+    let App {
+        tabs,
+        active,
+        input_buf,
+        config,
+        ..
+    } = app;
+    let Some(tab) = tabs.get_mut(*active) else {
+        return;
+    };
+    let mut sent = None;
+    egui::TopBottomPanel::bottom("input").show(ctx, |ui| {
+        // Vor dem TextEdit abgreifen, sonst springt ↑/↓ nur an Anfang/Ende der Zeile
+        if tab.input_focused {
+            let (up, down) = ui.input_mut(|i| {
+                (
+                    i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                    i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                )
+            });
+            if up || down {
+                recall_history(tab, input_buf, up);
+            }
+        }
+        ui.horizontal(|ui| {
+            ui.label("›");
+            let edit = egui::TextEdit::singleline(input_buf)
+                .font(egui::TextStyle::Monospace)
+                .desired_width(f32::INFINITY)
+                .hint_text("Befehl – Enter sendet");
+            let resp = ui.add(edit);
+            // Mit Eingabezeile bekommt sie den Fokus statt des Terminals
+            if std::mem::take(&mut tab.want_focus) {
+                resp.request_focus();
+            }
+            if resp.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                sent = Some(std::mem::take(input_buf));
+                resp.request_focus();
+            }
+            tab.input_focused = resp.has_focus() || sent.is_some();
+        });
+    });
+
+    let Some(line) = sent else { return };
+    if tab.confirm_send && !line.trim().is_empty() {
+        tab.pending_command = Some(line);
+        return;
+    }
+    submit_line(tab, line, config);
+}
+
+/// Zeile mit CR an den Worker, in den Verlauf und ggf. ins Profil.
+fn submit_line(tab: &mut SessionTab, line: String, config: &mut StarrConfig) {
+    let Some(tx) = &tab.tx else { return };
+    let _ = tx.send(ToWorker::SendText(format!("{line}\r")));
+    if config.behavior.local_echo {
+        append_local_echo(tab, &format!("{line}\r\n"));
+    }
+    tab.history_pos = None;
+    if line.trim().is_empty() || tab.input_history.last() == Some(&line) {
+        return;
+    }
+    tab.input_history.push(line);
+    if tab.input_history.len() > INPUT_HISTORY_MAX {
+        tab.input_history.remove(0);
+    }
+    if !config.behavior.persist_input_history {
+        return;
+    }
+    if let Some(sp) = tab.profile_name.as_deref().and_then(|n| config.find_mut(n)) {
+        sp.input_history = tab.input_history.clone();
+        // Verlauf ist Komfort – scheitert das Speichern, geht es ohne weiter
+        let _ = config.save();
+    }
+}
+
+/// Eine Zeile im Verlauf zurück (`older`) bzw. vor; hinter der neuesten ist die Zeile leer.
+fn recall_history(tab: &mut SessionTab, buf: &mut String, older: bool) {
+    let n = tab.input_history.len();
+    if n == 0 {
+        return;
+    }
+    tab.history_pos = match (tab.history_pos, older) {
+        (None, true) => Some(n - 1),
+        (None, false) => None,
+        (Some(p), true) => Some(p.saturating_sub(1)),
+        (Some(p), false) => (p + 1 < n).then_some(p + 1),
+    };
+    *buf = tab
+        .history_pos
+        .map(|p| tab.input_history[p].clone())
+        .unwrap_or_default();
+}
+
+fn find_bar(tab: &mut SessionTab, ctx: &egui::Context) {
+    let mut changed = false;
+    let mut step: Option<bool> = None; // Some(true) = weiter, Some(false) = zurück
+    egui::TopBottomPanel::bottom("find").show(ctx, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Suchen");
+            let resp = ui.add(egui::TextEdit::singleline(&mut tab.find.query).desired_width(240.0));
+            if std::mem::take(&mut tab.find.focus) {
+                resp.request_focus();
+            }
+            changed |= resp.changed();
+            if resp.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                step = Some(!ui.input(|i| i.modifiers.shift));
+                resp.request_focus();
+            }
+            changed |= ui
+                .checkbox(&mut tab.find.case_sensitive, "Aa")
+                .on_hover_text("Groß-/Kleinschreibung beachten")
+                .changed();
+            if ui.small_button("▲").clicked() {
+                step = Some(false);
+            }
+            if ui.small_button("▼").clicked() {
+                step = Some(true);
+            }
+            match tab.find.matches.len() {
+                0 if !tab.find.query.is_empty() => {
+                    ui.weak("keine Treffer");
                 }
-                Ok(FromWorker::Data(chunk)) => {
-                    // 200 KB Limit → deutlich weniger GPU
-                    append_and_limit(&mut app.view_buf, &chunk, 200_000);
-                    app.ansi_dirty = true;
+                0 => {}
+                n => {
+                    ui.weak(format!("{}/{n}", tab.find.current + 1));
                 }
-                Ok(FromWorker::Closed(msg)) => {
-                    app.connected = false;
-                    app.connect_error = Some(format!("Verbindung beendet: {msg}"));
-                    app.tx = None;
-                    drop_rx = true;
-                    break;
+            }
+            if ui.small_button("✖").clicked() || ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                tab.find = FindState::default();
+                tab.want_focus = true;
+                changed = true;
+            }
+        });
+    });
+
+    if changed {
+        tab.find.matches.clear();
+        tab.find.current = 0;
+        tab.ansi_dirty = true;
+        // Treffer sofort berechnen, damit Enter direkt springen kann
+        if !tab.find.query.is_empty() {
+            tab.find.matches =
+                find_matches(&tab.display_buf, &tab.find.query, tab.find.case_sensitive);
+            if let Some(m) = tab.find.matches.last() {
+                tab.find.current = tab.find.matches.len() - 1;
+                tab.find.jump = Some(m.start);
+                tab.autoscroll = false;
+            }
+        }
+    }
+    if let (Some(forward), false) = (step, tab.find.matches.is_empty()) {
+        let n = tab.find.matches.len();
+        tab.find.current = if forward {
+            (tab.find.current + 1) % n
+        } else {
+            (tab.find.current + n - 1) % n
+        };
+        tab.find.jump = Some(tab.find.matches[tab.find.current].start);
+        tab.autoscroll = false;
+        tab.ansi_dirty = true;
+    }
+}
+
+/// Alle Vorkommen von `query` in `text` als Zeichenbereiche.
+fn find_matches(text: &str, query: &str, case_sensitive: bool) -> Vec<std::ops::Range<usize>> {
+    let fold = |c: char| {
+        if case_sensitive {
+            c
+        } else {
+            c.to_lowercase().next().unwrap_or(c)
+        }
+    };
+    let hay: Vec<char> = text.chars().map(fold).collect();
+    let needle: Vec<char> = query.chars().map(fold).collect();
+    if needle.is_empty() || needle.len() > hay.len() {
+        return Vec::new();
+    }
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i + needle.len() <= hay.len() {
+        if hay[i..i + needle.len()] == needle[..] {
+            out.push(i..i + needle.len());
+            i += needle.len();
+        } else {
+            i += 1;
+        }
+    }
+    out
+}
+
+/// Hinterlegt Treffer farbig; `ranges` sind Zeichenbereiche im Job-Text.
+fn highlight_matches(job: &mut LayoutJob, ranges: &[std::ops::Range<usize>], current: usize) {
+    if ranges.is_empty() {
+        return;
+    }
+    // Zeichen → Byte-Offsets (Sections arbeiten auf Bytes)
+    let mut byte_of: Vec<usize> = job.text.char_indices().map(|(b, _)| b).collect();
+    byte_of.push(job.text.len());
+    let byte_ranges: Vec<(std::ops::Range<usize>, bool)> = ranges
+        .iter()
+        .enumerate()
+        .map(|(i, r)| (byte_of[r.start]..byte_of[r.end], i == current))
+        .collect();
+
+    let mut sections = Vec::with_capacity(job.sections.len() + 2 * byte_ranges.len());
+    for sec in std::mem::take(&mut job.sections) {
+        let mut pos = sec.byte_range.start;
+        for (r, is_current) in &byte_ranges {
+            if r.end <= pos || r.start >= sec.byte_range.end {
+                continue;
+            }
+            if r.start > pos {
+                sections.push(egui::text::LayoutSection {
+                    byte_range: pos..r.start,
+                    ..sec.clone()
+                });
+            }
+            let end = r.end.min(sec.byte_range.end);
+            let mut format = sec.format.clone();
+            format.background = if *is_current {
+                Color32::from_rgb(255, 140, 0)
+            } else {
+                Color32::from_rgb(120, 100, 0)
+            };
+            format.color = Color32::BLACK;
+            sections.push(egui::text::LayoutSection {
+                byte_range: pos.max(r.start)..end,
+                format,
+                ..sec.clone()
+            });
+            pos = end;
+        }
+        if pos < sec.byte_range.end {
+            sections.push(egui::text::LayoutSection {
+                byte_range: pos..sec.byte_range.end,
+                ..sec
+            });
+        }
+    }
+    job.sections = sections;
+}
+
+/// Log an/aus; Dateien landen unter <Konfig>/logs/<host>-<unix-zeit>.log.
+fn toggle_logging(tab: &mut SessionTab) {
+    let Some(tx) = &tab.tx else { return };
+    if tab.log_path.take().is_some() {
+        let _ = tx.send(ToWorker::StopLog);
+        return;
+    }
+    let dir = match starr_core::config_dir() {
+        Ok(d) => d.join("logs"),
+        Err(e) => {
+            tab.error = Some(format!("Log: {e}"));
+            return;
+        }
+    };
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        tab.error = Some(format!("Log: {e}"));
+        return;
+    }
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let path = dir.join(format!(
+        "{}-{stamp}.log",
+        tab.host.replace([':', '[', ']', '%'], "_")
+    ));
+    let _ = tx.send(ToWorker::StartLog(path.clone()));
+    tab.log_path = Some(path);
+}
+
+/// Bytes/s → "12.3 KB/s".
+fn fmt_rate(bytes_per_sec: f64) -> String {
+    format!("{}/s", fmt_bytes(bytes_per_sec as u64))
+}
+
+fn fmt_bytes(bytes: u64) -> String {
+    let bytes = bytes as f64;
+    if bytes >= 1024.0 * 1024.0 {
+        format!("{:.1} MB", bytes / (1024.0 * 1024.0))
+    } else {
+        format!("{:.1} KB", bytes / 1024.0)
+    }
+}
+
+/// Fehlermeldung + Hinweis, wenn der Server die gewählte Methode gar nicht anbietet.
+fn auth_error_text(e: &StarrError, profile: &StarrProfile) -> String {
+    let StarrError::Auth { allowed, .. } = e else {
+        return e.to_string();
+    };
+    let offers = |m: &str| allowed.iter().any(|a| a == m);
+    let hint = if allowed.is_empty() {
+        None
+    } else if profile.password.is_some() && !offers("password") && !offers("keyboard-interactive") {
+        Some("Passwort-Login ist auf dem Server abgeschaltet – Key oder Agent verwenden.")
+    } else if (profile.key_path.is_some() || !profile.identities.is_empty() || profile.use_agent)
+        && !offers("publickey")
+    {
+        Some("Der Server nimmt keine Keys an – Passwort verwenden.")
+    } else {
+        None
+    };
+    match hint {
+        Some(h) => format!("{e}\n{h}"),
+        None => e.to_string(),
+    }
+}
+
+/// 0 Sekunden = kein Timeout.
+fn secs_opt(secs: u64) -> Option<Duration> {
+    (secs > 0).then_some(Duration::from_secs(secs))
+}
+
+fn poll_worker(app: &mut App) {
+    if let Some(rx) = &app.test_rx {
+        match rx.try_recv() {
+            Ok(res) => {
+                app.test_result = Some(res);
+                app.test_rx = None;
+            }
+            Err(mpsc::TryRecvError::Empty) => {}
+            Err(mpsc::TryRecvError::Disconnected) => app.test_rx = None,
+        }
+    }
+    let mut failed = Vec::new();
+    for (i, tab) in app.tabs.iter_mut().enumerate() {
+        if let Some(f) = poll_tab(tab) {
+            failed.push((i, f));
+        }
+        let (bell, clipboard) = {
+            let mut screen = tab.screen.lock().unwrap();
+            (screen.take_bell(), screen.take_clipboard())
+        };
+        if bell {
+            ring_bell(tab, app.config.behavior.bell);
+        }
+        // OSC 52 vom Remote: nur mit ausdrücklicher Erlaubnis in die Zwischenablage
+        if let Some(text) = clipboard.filter(|_| app.config.security.remote_clipboard) {
+            copy_to_clipboard(&text);
+        }
+    }
+    // Gescheiterte Verbindungsversuche: Tab weg, zurück zur Connect-Card
+    for (i, f) in failed.into_iter().rev() {
+        close_tab(app, i);
+        match f {
+            FromWorker::UnknownHostKey(err) => app.pending_host_key = Some(err),
+            FromWorker::ConnectedErr(e) => app.connect_error = Some(e),
+            FromWorker::AuthErr(e) => {
+                // Altes Passwort nicht noch einmal abschicken
+                app.password.clear();
+                app.connect_error = Some(format!("Anmeldung fehlgeschlagen: {e}"));
+            }
+            _ => {}
+        }
+        app.show_connect = true;
+    }
+}
+
+/// Verarbeitet die Worker-Events eines Tabs. Liefert `ConnectedErr`/`AuthErr`/`UnknownHostKey`
+/// zurück – die betreffen das Connect-Formular, nicht den Tab.
+fn poll_tab(tab: &mut SessionTab) -> Option<FromWorker> {
+    let rx = tab.rx.as_ref()?;
+    let mut drop_rx = false;
+    let mut failed = None;
+    let mut closed = None;
+    loop {
+        match rx.try_recv() {
+            Ok(FromWorker::ConnectedOk(info)) => {
+                tab.connected = true;
+                tab.conn_info = Some(info);
+                tab.connected_at = Some(Instant::now());
+                tab.status = None;
+                tab.reconnecting = false;
+                tab.error = None;
+                // Größe neu an den frischen Worker melden
+                tab.last_cols = 0;
+                tab.last_rows = 0;
+                tab.ansi_dirty = true;
+                tab.last_ansi_build = Instant::now();
+                tab.want_focus = true;
+                if let (true, Some(tx)) = (tab.sftp.open, &tab.tx) {
+                    let _ = tx.send(ToWorker::Sftp(SftpCmd::List(".".into())));
+                    tab.sftp.loading = true;
                 }
-                Err(mpsc::TryRecvError::Empty) => break,
-                Err(mpsc::TryRecvError::Disconnected) => {
-                    app.connected = false;
-                    app.tx = None;
-                    drop_rx = true;
-                    break;
+            }
+            Ok(
+                ev @ (FromWorker::ConnectedErr(_)
+                | FromWorker::AuthErr(_)
+                | FromWorker::UnknownHostKey(_)),
+            ) => {
+                tab.connected = false;
+                tab.tx = None;
+                if tab.reconnecting {
+                    // Tab samt Scrollback behalten, Fehler dort anzeigen
+                    tab.reconnecting = false;
+                    let e = match ev {
+                        FromWorker::UnknownHostKey(e) => e.to_string(),
+                        FromWorker::ConnectedErr(e) => e,
+                        FromWorker::AuthErr(e) => format!("Anmeldung fehlgeschlagen: {e}"),
+                        _ => unreachable!("oben gefiltert"),
+                    };
+                    tab.error = Some(format!("Neu verbinden fehlgeschlagen: {e}"));
+                } else {
+                    failed = Some(ev);
+                }
+                drop_rx = true;
+                break;
+            }
+            Ok(FromWorker::AuthPrompt(p)) => {
+                tab.pending_prompt = Some(p);
+            }
+            Ok(FromWorker::Progress(ev)) => match ev {
+                SessionEvent::Connecting => tab.status = Some("Verbinde, Handshake …".into()),
+                SessionEvent::Handshaked => tab.status = Some("Handshake abgeschlossen …".into()),
+                SessionEvent::Negotiated { cipher, mac, .. } => {
+                    tab.crypto = Some(format!("{cipher} / {mac}"))
+                }
+                SessionEvent::Authenticating(m) => {
+                    tab.auth_method = Some(m);
+                    tab.status = Some(format!("Authentifiziere ({m}) …"));
+                }
+                SessionEvent::PasswordExpired => {
+                    tab.status = Some("Passwort abgelaufen – neues Passwort setzen …".into())
                 }
+                SessionEvent::Authenticated => tab.status = Some("Öffne Shell …".into()),
+                _ => {}
+            },
+            Ok(FromWorker::Frame(frame)) => {
+                // Lokale Änderungen können ein neueres Frame schon gesetzt haben
+                if frame.seq > tab.frame.seq {
+                    tab.frame = frame;
+                    tab.ansi_dirty = true;
+                }
+            }
+            Ok(FromWorker::RemoteEcho) => tab.remote_echo = true,
+            Ok(FromWorker::ForwardErr(id, e)) => {
+                tab.tunnels.retain(|(i, _)| *i != id);
+                tab.error = Some(format!("Tunnel: {e}"));
+            }
+            Ok(FromWorker::LogErr(e)) => {
+                tab.log_path = None;
+                tab.error = Some(format!("Log: {e}"));
+            }
+            Ok(FromWorker::Rate(down, up)) => {
+                tab.rate = Some((down, up));
+            }
+            Ok(FromWorker::SftpListing(dir, entries)) => {
+                tab.sftp.dir = dir.to_string_lossy().into_owned();
+                tab.sftp.entries = entries;
+                tab.sftp.sort_entries();
+                tab.sftp.loading = false;
+            }
+            Ok(FromWorker::SftpErr(e)) => {
+                tab.sftp.loading = false;
+                tab.error = Some(format!("SFTP: {e}"));
+            }
+            Ok(FromWorker::Transfer(label, done, total)) => {
+                tab.transfer = Some((label, done, total));
+            }
+            Ok(FromWorker::TransferDone) => tab.transfer = None,
+            Ok(FromWorker::Closed(msg)) => {
+                closed = Some(msg);
+                break;
+            }
+            Err(mpsc::TryRecvError::Empty) => break,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                tab.connected = false;
+                tab.tx = None;
+                drop_rx = true;
+                break;
             }
         }
     }
     if drop_rx {
-        app.rx = None;
+        tab.rx = None;
+    }
+    if let Some(msg) = closed {
+        tab.reset_connection();
+        tab.error = Some(format!("Verbindung beendet: {msg}"));
+    }
+    failed
+}
+
+/// So lange stehen Auth-Methode und Cipher nach dem Verbinden im Header.
+const CONNECTED_INFO: Duration = Duration::from_secs(5);
+
+/// „publickey · aes256-gcm@openssh.com / hmac-sha2-256“, soweit bekannt.
+fn connection_details(tab: &SessionTab) -> Option<String> {
+    match (tab.auth_method, &tab.crypto) {
+        (Some(m), Some(c)) => Some(format!("{m} · {c}")),
+        (Some(m), None) => Some(m.to_string()),
+        (None, Some(c)) => Some(c.clone()),
+        (None, None) => None,
+    }
+}
+
+/// Details für den Tooltip über „Verbunden“: Auth/Cipher, Server-Banner, Verfahren je Richtung.
+fn connection_tooltip(tab: &SessionTab) -> Option<String> {
+    let Some(info) = &tab.conn_info else {
+        return connection_details(tab);
+    };
+    let mut lines = Vec::new();
+    if let Some(m) = tab.auth_method {
+        lines.push(format!("Auth: {m}"));
+    }
+    lines.extend(algorithm_lines(info));
+    Some(lines.join("\n"))
+}
+
+/// Server-Banner und ausgehandelte Verfahren je Richtung, eine Zeile pro Angabe.
+fn algorithm_lines(info: &ConnectionInfo) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Some(b) = &info.banner {
+        lines.push(format!("Server: {b}"));
+    }
+    lines.push(format!("Kex: {}", info.kex));
+    lines.push(format!("Host-Key: {}", info.host_key));
+    lines.push(format!(
+        "Client → Server: {} / {}",
+        info.cipher_cs, info.mac_cs
+    ));
+    lines.push(format!(
+        "Server → Client: {} / {}",
+        info.cipher_sc, info.mac_sc
+    ));
+    lines
+}
+
+/// Alternate Screen, Maus, DECCKM und SGR zurücksetzen, Cursor wieder sichtbar.
+const RESET_MODES: &str =
+    "\x1b[?1049l\x1b[?1000l\x1b[?1002l\x1b[?1003l\x1b[?1006l\x1b[?1l\x1b[?25h\x1b[0m";
+
+/// Glocken, die schneller kommen, werden verschluckt (z. B. `cat` auf eine Binärdatei).
+const BELL_DEBOUNCE: Duration = Duration::from_millis(300);
+const BELL_FLASH: Duration = Duration::from_millis(100);
+
+fn ring_bell(tab: &mut SessionTab, mode: BellMode) {
+    let now = Instant::now();
+    if tab
+        .last_bell
+        .is_some_and(|t| now.duration_since(t) < BELL_DEBOUNCE)
+    {
+        return;
+    }
+    tab.last_bell = Some(now);
+    match mode {
+        BellMode::Flash => tab.flash_until = Some(now + BELL_FLASH),
+        BellMode::Sound => system_beep(),
+        BellMode::Off => {}
+    }
+}
+
+#[cfg(windows)]
+fn system_beep() {
+    // MB_OK = Standard-Systemton
+    unsafe {
+        windows_sys::Win32::System::Diagnostics::Debug::MessageBeep(0);
+    }
+}
+
+/// Ohne Windows-API: BEL auf stderr – klingelt, wenn Starr aus einem Terminal gestartet wurde.
+#[cfg(not(windows))]
+fn system_beep() {
+    use std::io::Write;
+    let mut err = std::io::stderr();
+    let _ = err.write_all(b"\x07");
+    let _ = err.flush();
+}
+
+/// „Trennen“: Worker beenden und einsammeln, der Tab bleibt mit seinem Scrollback
+/// stehen. Danach die Connect-Card mit dem Profil der Verbindung im Formular.
+fn disconnect_tab(app: &mut App, i: usize) {
+    let Some(tab) = app.tabs.get_mut(i) else {
+        return;
+    };
+    if let Some(p) = tab.pending_prompt.take() {
+        let _ = p.reply.send(vec![String::new(); p.prompts.len()]);
+    }
+    if let Some(tx) = &tab.tx {
+        let _ = tx.send(ToWorker::Close);
+    }
+    // Verbunden → der Worker ist in seiner Schleife und endet gleich (Session-Close ≤ 3 s).
+    // Noch im Verbindungsaufbau → nicht blockieren, er liest `Close` danach und endet dann.
+    let worker = tab.worker.take();
+    if tab.connected {
+        if let Some(h) = worker {
+            let _ = h.join();
+        }
+    }
+    tab.reset_connection();
+    tab.status = None;
+    tab.reconnecting = false;
+    tab.error = None;
+    tab.update_screen(|s| s.feed("\r\n[Getrennt]\r\n"));
+
+    let form = match tab.profile_name.as_deref().and_then(|n| app.config.find(n)) {
+        Some(sp) => Some(sp.clone()),
+        None => tab
+            .last_connect
+            .as_ref()
+            .map(|(p, _)| SavedProfile::new("", p.clone())),
+    };
+    if let Some(form) = form {
+        load_profile_into_form(app, &form);
+    }
+    app.show_connect = true;
+    app.connect_error = None;
+}
+
+/// Schließt einen Tab samt Worker; offene Auth-Abfrage wird leer beantwortet.
+fn close_tab(app: &mut App, i: usize) {
+    let tab = app.tabs.remove(i);
+    if let Some(p) = tab.pending_prompt {
+        let _ = p.reply.send(vec![String::new(); p.prompts.len()]);
+    }
+    if let Some(tx) = &tab.tx {
+        let _ = tx.send(ToWorker::Close);
+    }
+    if i < app.active || app.active >= app.tabs.len() {
+        app.active = app.active.saturating_sub(1);
+    }
+    // Pane des geschlossenen Tabs → Teilung aufheben, sonst Indizes nachziehen
+    if let Some(sp) = app.split.as_mut() {
+        if sp.panes.contains(&i) {
+            app.split = None;
+        } else {
+            sp.panes = sp.panes.map(|p| if p > i { p - 1 } else { p });
+        }
+    }
+}
+
+/// Tab-Leiste: Tab wählen, mit ✖ schließen, „+“ öffnet die Connect-Card.
+fn tab_strip(app: &mut App, ctx: &egui::Context) {
+    let mut close = None;
+    let mut select = None;
+    let mut toggle = None;
+    egui::TopBottomPanel::top("tabs").show(ctx, |ui| {
+        ui.horizontal(|ui| {
+            for (i, tab) in app.tabs.iter().enumerate() {
+                let selected = i == app.active && !app.show_connect;
+                let dot = if tab.connected { "●" } else { "○" };
+                let label = match &tab.label {
+                    Some(l) => format!("{dot} [{l}] {}", tab.title),
+                    None => format!("{dot} {}", tab.title),
+                };
+                let resp = ui.selectable_label(selected, label);
+                // Profilfarbe: Tönung + Streifen unten, damit z. B. Produktion nicht zu übersehen ist
+                if let Some(c) = tab.color {
+                    let r = resp.rect;
+                    ui.painter().rect_filled(
+                        r,
+                        2.0,
+                        c.gamma_multiply(if selected { 0.35 } else { 0.2 }),
+                    );
+                    let strip = egui::Rect::from_min_max(egui::pos2(r.min.x, r.max.y - 3.0), r.max);
+                    ui.painter().rect_filled(strip, 0.0, c);
+                }
+                let resp = match tab.screen.lock().unwrap().title() {
+                    "" => resp,
+                    t => resp.on_hover_text(t),
+                };
+                if resp.clicked() {
+                    select = Some(i);
+                }
+                if ui
+                    .small_button("✖")
+                    .on_hover_text("Session schließen")
+                    .clicked()
+                {
+                    close = Some(i);
+                }
+                ui.separator();
+            }
+            if ui
+                .selectable_label(app.show_connect, "+")
+                .on_hover_text("Neue Verbindung")
+                .clicked()
+            {
+                app.show_connect = true;
+                app.connect_error = None;
+            }
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                let split = app.split.as_ref().map(|sp| sp.vertical);
+                if ui
+                    .selectable_label(split == Some(false), "⬍")
+                    .on_hover_text("Übereinander teilen (Ctrl+Shift+O)")
+                    .clicked()
+                {
+                    toggle = Some(false);
+                }
+                if ui
+                    .selectable_label(split == Some(true), "⬌")
+                    .on_hover_text("Nebeneinander teilen (Ctrl+Shift+E)")
+                    .clicked()
+                {
+                    toggle = Some(true);
+                }
+            });
+        });
+    });
+    if let Some(i) = select {
+        select_tab(app, i);
+    }
+    if let Some(vertical) = toggle {
+        toggle_split(app, vertical);
+    }
+    if let Some(i) = close {
+        close_tab(app, i);
     }
 }
 
-/// Hängt `chunk` an und kappt am Anfang, wenn `max_len` überschritten.
-fn append_and_limit(buf: &mut String, chunk: &str, max_len: usize) {
-    buf.push_str(chunk);
-    if buf.len() > max_len {
-        let cut = buf.len() - max_len;
-        // an char-Grenze schneiden:
-        let mut cut_b = cut;
-        for (i, _) in buf.char_indices() {
-            if i >= cut { cut_b = i; break; }
+/// Tab aktivieren; liegt er nicht in der Teilung, ersetzt er das fokussierte Pane.
+fn select_tab(app: &mut App, i: usize) {
+    if let Some(sp) = app.split.as_mut() {
+        if !sp.panes.contains(&i) {
+            let slot = sp.panes.iter().position(|&p| p == app.active).unwrap_or(0);
+            sp.panes[slot] = i;
         }
-        buf.drain(..cut_b);
+    }
+    app.active = i;
+    app.show_connect = false;
+    if let Some(tab) = app.tabs.get_mut(i) {
+        tab.want_focus = true;
     }
 }
 
 /// Sichere UTF-8 Scheibe aus char-Indizes.
 fn safe_slice(s: &str, start_char: usize, end_char: usize) -> Option<&str> {
-    let to_byte = |s: &str, cidx: usize| {
-        if cidx == 0 { return 0; }
-        let mut count = 0usize;
-        for (i, _) in s.char_indices() {
-            if count == cidx { return i; }
-            count += 1;
-        }
-        s.len()
-    };
+    let to_byte = |s: &str, cidx: usize| s.char_indices().nth(cidx).map_or(s.len(), |(i, _)| i);
     let b0 = to_byte(s, start_char);
     let b1 = to_byte(s, end_char);
-    if b0 <= b1 && b1 <= s.len() { Some(&s[b0..b1]) } else { None }
+    if b0 <= b1 && b1 <= s.len() {
+        Some(&s[b0..b1])
+    } else {
+        None
+    }
+}
+
+fn rgb(c: Rgb) -> Color32 {
+    let [r, g, b] = c.0;
+    Color32::from_rgb(r, g, b)
+}
+
+/// Name der Vorlage, der die Palette exakt entspricht, sonst „Eigene“.
+fn preset_name(palette: &Palette) -> &'static str {
+    Palette::PRESETS
+        .iter()
+        .find(|(_, p)| p() == *palette)
+        .map_or("Eigene", |(name, _)| *name)
+}
+
+fn term_color(c: TermColor, palette: &Palette, default: Color32) -> Color32 {
+    match c {
+        TermColor::Default => default,
+        TermColor::Indexed(n) => xterm_256(n, palette),
+        TermColor::Rgb(r, g, b) => Color32::from_rgb(r, g, b),
+    }
+}
+
+/// Zellattribute → egui-Format; `cursor` invertiert die Zelle.
+fn cell_format(a: CellAttrs, palette: &Palette, font: &FontId, cursor: bool) -> TextFormat {
+    let mut fg = term_color(a.fg, palette, rgb(palette.foreground));
+    if a.bold {
+        // Kein fetter Monospace-Font registriert → aufhellen wie viele Terminals
+        let up = |c: u8| c.saturating_add((255 - c) / 3);
+        fg = Color32::from_rgb(up(fg.r()), up(fg.g()), up(fg.b()));
+    }
+    let bg = term_color(a.bg, palette, Color32::TRANSPARENT);
+    let (color, background) = if a.reverse != cursor {
+        (
+            if bg == Color32::TRANSPARENT {
+                rgb(palette.background)
+            } else {
+                bg
+            },
+            fg,
+        )
+    } else {
+        (fg, bg)
+    };
+    TextFormat {
+        font_id: font.clone(),
+        color,
+        background,
+        italics: a.italic,
+        underline: if a.underline {
+            egui::Stroke::new(1.0, color)
+        } else {
+            egui::Stroke::NONE
+        },
+        ..Default::default()
+    }
+}
+
+/// Fertig formatierte Scrollback-Zeilen. Die ändern sich nicht mehr (siehe
+/// `Screen::scrolled_lines`), neu formatiert wird nur das sichtbare Grid.
+#[derive(Default)]
+struct LineCache {
+    /// Nummer der ersten Zeile in `lines` (Zählung von `Screen::scrolled_lines`)
+    first: u64,
+    lines: VecDeque<LayoutJob>,
+    /// Palette/Schrift, mit der formatiert wurde; ändert sich die, ist alles ungültig
+    style: Option<(Palette, FontId)>,
+}
+
+impl LineCache {
+    /// Auf den Scrollback von `screen` bringen, aber nur bis `upto` (Stand des Frames –
+    /// der Worker kann schon weiter sein): Herausgefallenes vorne weg, Neues hinten formatieren.
+    fn sync(&mut self, screen: &Screen, upto: u64, palette: &Palette, font: &FontId) {
+        let total = screen.scrolled_lines();
+        let first = total - screen.scrollback_len() as u64;
+        let upto = upto.clamp(first, total);
+        let same_style = matches!(&self.style, Some((p, f)) if p == palette && f == font);
+        // Anderer Stil, Reset oder neuer Screen → alles neu
+        if !same_style || self.first > first || self.first + self.lines.len() as u64 > upto {
+            self.lines.clear();
+            self.style = Some((palette.clone(), font.clone()));
+            self.first = first;
+        }
+        let dropped = (first - self.first).min(self.lines.len() as u64) as usize;
+        self.lines.drain(..dropped);
+        self.first = first;
+        let cached = self.lines.len();
+        for line in screen
+            .lines()
+            .skip(cached)
+            .take((upto - first) as usize - cached)
+        {
+            self.lines
+                .push_back(spans_to_layout_job(&line_spans(line, None), palette, font));
+        }
+    }
 }
 
-/// ANSI → LayoutJob (SGR 0, 30–37, 90–97)
-fn ansi_to_layout_job(s: &str) -> LayoutJob {
-    use ansi_parser::{AnsiParser, AnsiSequence, Output};
+/// Frame (+ Scrollback aus dem Cache) → (Text, LayoutJob) mit identischem Text,
+/// damit Auswahl/Kopieren passen.
+fn screen_to_layout_job(
+    frame: &Frame,
+    screen: &Screen,
+    cache: &mut LineCache,
+    palette: &Palette,
+    font: &FontId,
+) -> (String, LayoutJob) {
+    cache.sync(screen, frame.scrolled, palette, font);
     let mut job = LayoutJob::default();
-    let mut color = Color32::from_rgb(230, 230, 230);
-    let font = FontId::monospace(15.0);
-    let mut fmt = TextFormat { font_id: font.clone(), color, ..Default::default() };
-
-    for item in s.ansi_parse() {
-        match item {
-            Output::TextBlock(txt) => job.append(&txt, 0.0, fmt.clone()),
-            Output::Escape(AnsiSequence::SetGraphicsMode(params)) => {
-                for p in params {
-                    match p as u8 {
-                        0  => { color = Color32::from_rgb(230,230,230); fmt.color = color; }
-                        30 => { color = Color32::from_rgb(0,0,0);      fmt.color = color; }
-                        31 => { color = Color32::from_rgb(205,49,49);  fmt.color = color; }
-                        32 => { color = Color32::from_rgb(13,188,121); fmt.color = color; }
-                        33 => { color = Color32::from_rgb(229,229,16); fmt.color = color; }
-                        34 => { color = Color32::from_rgb(36,114,200); fmt.color = color; }
-                        35 => { color = Color32::from_rgb(188,63,188); fmt.color = color; }
-                        36 => { color = Color32::from_rgb(17,168,205); fmt.color = color; }
-                        37 => { color = Color32::from_rgb(229,229,229);fmt.color = color; }
-                        90 => { color = Color32::from_rgb(102,102,102);fmt.color = color; }
-                        91 => { color = Color32::from_rgb(241,76,76);  fmt.color = color; }
-                        92 => { color = Color32::from_rgb(35,209,139); fmt.color = color; }
-                        93 => { color = Color32::from_rgb(245,245,67); fmt.color = color; }
-                        94 => { color = Color32::from_rgb(59,142,234); fmt.color = color; }
-                        95 => { color = Color32::from_rgb(214,112,214);fmt.color = color; }
-                        96 => { color = Color32::from_rgb(41,184,219); fmt.color = color; }
-                        97 => { color = Color32::from_rgb(255,255,255);fmt.color = color; }
-                        _ => {}
-                    }
-                }
-            }
-            _ => {}
+    let newline = cell_format(CellAttrs::default(), palette, font, false);
+
+    let mut first = true;
+    let mut push = |job: &mut LayoutJob, part: &LayoutJob| {
+        if !std::mem::take(&mut first) {
+            job.append("\n", 0.0, newline.clone());
         }
+        append_job(job, part);
+    };
+    for part in &cache.lines {
+        push(&mut job, part);
+    }
+    for line in &frame.grid {
+        push(&mut job, &spans_to_layout_job(line, palette, font));
+    }
+    (job.text.clone(), job)
+}
+
+/// Eine Zeile als eigener Job; der Cursor-Span wird invertiert.
+fn spans_to_layout_job(spans: &[Span], palette: &Palette, font: &FontId) -> LayoutJob {
+    let mut job = LayoutJob::default();
+    for span in spans {
+        job.append(
+            &span.text,
+            0.0,
+            cell_format(span.attrs, palette, font, span.cursor),
+        );
     }
     job
 }
 
+/// `part` hinten an `job` hängen (Byte-Bereiche der Abschnitte verschoben).
+fn append_job(job: &mut LayoutJob, part: &LayoutJob) {
+    let offset = job.text.len();
+    job.text.push_str(&part.text);
+    job.sections
+        .extend(part.sections.iter().map(|s| LayoutSection {
+            byte_range: s.byte_range.start + offset..s.byte_range.end + offset,
+            ..s.clone()
+        }));
+}
+
+/// xterm-256: 0–15 aus der Palette, 16–231 6×6×6-Würfel, 232–255 Graustufen.
+fn xterm_256(n: u8, palette: &Palette) -> Color32 {
+    match n {
+        0..=15 => rgb(palette.ansi[n as usize]),
+        16..=231 => {
+            let i = n - 16;
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            Color32::from_rgb(level(i / 36), level(i / 6 % 6), level(i % 6))
+        }
+        _ => {
+            let v = 8 + (n - 232) * 10;
+            Color32::from_rgb(v, v, v)
+        }
+    }
+}
+
 /// Keyboard → xterm-Sequenzen (Ctrl+C/D/Z NICHT abfangen)
-fn map_key(k: egui::Key, m: egui::Modifiers) -> Option<String> {
+/// `app_cursor`: Remote hat DECCKM gesetzt → Pfeile/Home/End als `ESC O x`.
+fn map_key(k: egui::Key, m: egui::Modifiers, app_cursor: bool) -> Option<String> {
     use egui::Key::*;
+    if let Some(seq) = function_key(k, m) {
+        return Some(seq);
+    }
     if m.ctrl || m.command {
         return match k {
-            V => paste_from_clipboard(),
-            // C/D/Z NICHT abfangen -> None
-            _ => None,
+            // Ctrl+V: Einfügen (siehe Eingabe), nicht ^V senden
+            V => None,
+            // Ctrl+Shift+… gehört der App (Kopieren, Teilen, …)
+            _ if m.shift => None,
+            // Ctrl+A … Ctrl+Z → 0x01 … 0x1a (Ctrl+C = Interrupt, Ctrl+D = EOF)
+            _ => key_char(k, m)
+                .filter(char::is_ascii_alphabetic)
+                .map(|c| char::from(c as u8 & 0x1f).to_string()),
         };
     }
+    let csi = if app_cursor { "\x1bO" } else { "\x1b[" };
     match k {
         Enter => Some("\r".into()),
         Tab => Some("\t".into()),
         Backspace => Some("\x7f".into()),
         Delete => Some("\x1b[3~".into()),
-        ArrowUp => Some("\x1b[A".into()),
-        ArrowDown => Some("\x1b[B".into()),
-        ArrowRight => Some("\x1b[C".into()),
-        ArrowLeft => Some("\x1b[D".into()),
-        Home => Some("\x1b[H".into()),
-        End => Some("\x1b[F".into()),
+        ArrowUp => Some(format!("{csi}A")),
+        ArrowDown => Some(format!("{csi}B")),
+        ArrowRight => Some(format!("{csi}C")),
+        ArrowLeft => Some(format!("{csi}D")),
+        Home => Some(format!("{csi}H")),
+        End => Some(format!("{csi}F")),
         PageUp => Some("\x1b[5~".into()),
         PageDown => Some("\x1b[6~".into()),
         _ => None,
     }
 }
 
+/// Buchstabe/Ziffer einer Taste (für Alt+ und Ctrl+Taste, die keinen Text liefern).
+fn key_char(k: egui::Key, m: egui::Modifiers) -> Option<char> {
+    let mut chars = k.name().chars();
+    let c = chars
+        .next()
+        .filter(|c| c.is_ascii_alphanumeric() && chars.next().is_none())?;
+    Some(if m.shift {
+        c.to_ascii_uppercase()
+    } else {
+        c.to_ascii_lowercase()
+    })
+}
+
+/// F1–F12 und Insert; mit Shift/Alt/Ctrl als xterm-Modifier-Parameter (`ESC[15;5~` = Ctrl+F5).
+fn function_key(k: egui::Key, m: egui::Modifiers) -> Option<String> {
+    use egui::Key::*;
+    let mods = 1 + u8::from(m.shift) + 2 * u8::from(m.alt) + 4 * u8::from(m.ctrl);
+    let ss3 = |c: char| {
+        if mods == 1 {
+            format!("\x1bO{c}")
+        } else {
+            format!("\x1b[1;{mods}{c}")
+        }
+    };
+    let tilde = |n: u8| {
+        if mods == 1 {
+            format!("\x1b[{n}~")
+        } else {
+            format!("\x1b[{n};{mods}~")
+        }
+    };
+    Some(match k {
+        F1 => ss3('P'),
+        F2 => ss3('Q'),
+        F3 => ss3('R'),
+        F4 => ss3('S'),
+        F5 => tilde(15),
+        F6 => tilde(17),
+        F7 => tilde(18),
+        F8 => tilde(19),
+        F9 => tilde(20),
+        F10 => tilde(21),
+        F11 => tilde(23),
+        F12 => tilde(24),
+        Insert => tilde(2),
+        _ => return None,
+    })
+}
+
+/// Eine Clipboard-Instanz für die ganze App. Unter X11 muss sie leben bleiben,
+/// sonst ist kopierter Text weg, sobald sie gedroppt wird.
+fn with_clipboard<T>(f: impl FnOnce(&mut arboard::Clipboard) -> Option<T>) -> Option<T> {
+    static CLIPBOARD: std::sync::Mutex<Option<arboard::Clipboard>> = std::sync::Mutex::new(None);
+    let mut guard = CLIPBOARD.lock().ok()?;
+    if guard.is_none() {
+        *guard = arboard::Clipboard::new().ok();
+    }
+    f(guard.as_mut()?)
+}
+
 fn copy_to_clipboard(text: &str) {
-    #[cfg(windows)]
-    let _ = clipboard_win::set_clipboard_string(text);
+    with_clipboard(|c| c.set_text(text).ok());
 }
 
 fn paste_from_clipboard() -> Option<String> {
-    #[cfg(windows)]
-    { clipboard_win::get_clipboard_string().ok() }
-    #[cfg(not(windows))]
-    { None }
+    with_clipboard(|c| c.get_text().ok())
+}
+
+/// X11/Wayland: Auswahl zusätzlich als PRIMARY, damit Mittelklick überall einfügt.
+#[cfg(target_os = "linux")]
+fn copy_to_primary(text: &str) {
+    use arboard::{LinuxClipboardKind, SetExtLinux};
+    with_clipboard(|c| {
+        c.set()
+            .clipboard(LinuxClipboardKind::Primary)
+            .text(text)
+            .ok()
+    });
+}
+
+#[cfg(not(target_os = "linux"))]
+fn copy_to_primary(_text: &str) {}
+
+#[cfg(target_os = "linux")]
+fn paste_from_primary() -> Option<String> {
+    use arboard::{GetExtLinux, LinuxClipboardKind};
+    with_clipboard(|c| c.get().clipboard(LinuxClipboardKind::Primary).text().ok())
+        .filter(|t| !t.is_empty())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn paste_from_primary() -> Option<String> {
+    None
 }
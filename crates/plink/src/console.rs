@@ -0,0 +1,125 @@
+//! Windows-Konsole im Raw-Modus: kein Zeilenpuffer, kein Echo, Ctrl+C als Byte,
+//! Pfeil-/Funktionstasten als VT-Sequenzen. Ausgabe versteht ANSI.
+//! Ist stdin keine Konsole (Pipe von WinSCP), bleibt alles wie es ist.
+//! Dazu: Zeile ohne Echo lesen (Passwort-Abfrage).
+
+use std::io::{self, BufRead, Write};
+
+fn read_line() -> io::Result<String> {
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line)?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::sync::Mutex;
+    use windows_sys::Win32::System::Console::{
+        GetConsoleMode, GetStdHandle, SetConsoleMode, DISABLE_NEWLINE_AUTO_RETURN,
+        ENABLE_ECHO_INPUT, ENABLE_LINE_INPUT, ENABLE_PROCESSED_INPUT,
+        ENABLE_VIRTUAL_TERMINAL_INPUT, ENABLE_VIRTUAL_TERMINAL_PROCESSING, STD_INPUT_HANDLE,
+        STD_OUTPUT_HANDLE,
+    };
+
+    /// Ursprüngliche Modi (stdin, stdout), solange Raw aktiv ist
+    static SAVED: Mutex<Option<(u32, Option<u32>)>> = Mutex::new(None);
+
+    pub fn enable_raw() -> bool {
+        unsafe {
+            let input = GetStdHandle(STD_INPUT_HANDLE);
+            let output = GetStdHandle(STD_OUTPUT_HANDLE);
+            let mut in_mode = 0;
+            if GetConsoleMode(input, &mut in_mode) == 0 {
+                return false;
+            }
+            let raw = (in_mode & !(ENABLE_LINE_INPUT | ENABLE_ECHO_INPUT | ENABLE_PROCESSED_INPUT))
+                | ENABLE_VIRTUAL_TERMINAL_INPUT;
+            if SetConsoleMode(input, raw) == 0 {
+                return false;
+            }
+            let mut out_mode = 0;
+            let out_saved = (GetConsoleMode(output, &mut out_mode) != 0).then_some(out_mode);
+            if out_saved.is_some() {
+                SetConsoleMode(
+                    output,
+                    out_mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING | DISABLE_NEWLINE_AUTO_RETURN,
+                );
+            }
+            *SAVED.lock().unwrap() = Some((in_mode, out_saved));
+            true
+        }
+    }
+
+    pub fn restore() {
+        let Some((in_mode, out_mode)) = SAVED.lock().unwrap().take() else {
+            return;
+        };
+        unsafe {
+            SetConsoleMode(GetStdHandle(STD_INPUT_HANDLE), in_mode);
+            if let Some(m) = out_mode {
+                SetConsoleMode(GetStdHandle(STD_OUTPUT_HANDLE), m);
+            }
+        }
+    }
+
+    pub fn read_hidden_line() -> std::io::Result<String> {
+        unsafe {
+            let input = GetStdHandle(STD_INPUT_HANDLE);
+            let mut mode = 0;
+            let console = GetConsoleMode(input, &mut mode) != 0;
+            if console {
+                SetConsoleMode(input, mode & !ENABLE_ECHO_INPUT);
+            }
+            let line = super::read_line();
+            if console {
+                SetConsoleMode(input, mode);
+            }
+            line
+        }
+    }
+}
+
+#[cfg(not(windows))]
+mod imp {
+    pub fn enable_raw() -> bool {
+        false
+    }
+
+    pub fn restore() {}
+
+    pub fn read_hidden_line() -> std::io::Result<String> {
+        let fd = libc::STDIN_FILENO;
+        let mut term = std::mem::MaybeUninit::<libc::termios>::uninit();
+        // SAFETY: tcgetattr füllt `term` vollständig, wenn es 0 liefert (sonst kein TTY)
+        let saved =
+            unsafe { (libc::tcgetattr(fd, term.as_mut_ptr()) == 0).then(|| term.assume_init()) };
+        if let Some(t) = saved {
+            let mut quiet = t;
+            quiet.c_lflag &= !libc::ECHO;
+            // SAFETY: gültige termios-Struktur von tcgetattr
+            unsafe { libc::tcsetattr(fd, libc::TCSANOW, &quiet) };
+        }
+        let line = super::read_line();
+        if let Some(t) = saved {
+            // SAFETY: wie oben, ursprüngliche Einstellungen zurück
+            unsafe { libc::tcsetattr(fd, libc::TCSANOW, &t) };
+        }
+        line
+    }
+}
+
+/// Schaltet stdin in den Raw-Modus; `false`, wenn es keine Konsole ist.
+pub use imp::enable_raw;
+/// Stellt die Modi von vor `enable_raw` wieder her. Vor `process::exit` aufrufen –
+/// dort laufen keine Destruktoren.
+pub use imp::restore;
+
+/// `prompt` auf stderr, dann eine Zeile von stdin lesen, ohne sie anzuzeigen.
+pub fn read_hidden(prompt: &str) -> io::Result<String> {
+    eprint!("{prompt}");
+    io::stderr().flush()?;
+    let line = imp::read_hidden_line();
+    // Das Enter wurde nicht geechot
+    eprintln!();
+    line
+}
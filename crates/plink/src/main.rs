@@ -1,10 +1,20 @@
 use anyhow::{anyhow, Result};
 use clap::Parser;
-use starr_core::{StarrProfile, StarrSession};
-use std::io::{self, Read};
+use starr_core::{
+    check_key_file, default_ssh_config_path, key_file_is_encrypted, key_file_warning,
+    resolve_ssh_host, split_host_port, trust_host_key, AuthPrompt, ConnectError, ConnectionInfo,
+    ForwardSpec, LogMode, PromptResponder, ReaderConfig, SessionEvent, StarrError, StarrProfile,
+    StarrSession, Utf8Stream,
+};
+use std::io::{self, BufRead, IsTerminal, Read, Write};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
 use std::thread;
 use std::time::Duration;
 
+mod console;
+
 /// Minimaler Plink-Klon (WinSCP-kompatibel genug fürs Daily-Use)
 /// Beispiele:
 ///   starr-plink -ssh -P 22 -l user host -pw geheim
@@ -23,17 +33,17 @@ struct Args {
     /// host oder [user@]host (kann fehlen, wenn WinSCP uns den Host in EXTRAS reinwirft)
     host: Option<String>,
 
-    /// -P <port>
-    #[arg(short = 'P', long = "port", default_value_t = 22)]
-    port: u16,
+    /// -P <port> (schlägt ein Port in host:port; ohne beides 22)
+    #[arg(short = 'P', long = "port")]
+    port: Option<u16>,
 
-    /// -l <user>
+    /// -l <user> (schlägt user@host, wie bei plink/OpenSSH)
     #[arg(short = 'l', long = "user")]
     user: Option<String>,
 
-    /// -i <keyfile> (OpenSSH)
+    /// -i <keyfile> (OpenSSH); mehrfach → der Reihe nach probieren
     #[arg(short = 'i', long = "identity")]
-    identity: Option<String>,
+    identity: Vec<String>,
 
     /// -pw <password>
     #[arg(long = "pw")]
@@ -43,12 +53,134 @@ struct Args {
     #[arg(long = "pass")]
     passphrase: Option<String>,
 
+    /// -connect-timeout <Sekunden> für den TCP-Connect
+    #[arg(long = "connect-timeout")]
+    connect_timeout: Option<u64>,
+
+    /// -handshake-timeout <Sekunden> für SSH-Handshake und Anmeldung (langsame Leitungen: erhöhen)
+    #[arg(long = "handshake-timeout")]
+    handshake_timeout: Option<u64>,
+
+    /// -keepalive <Sekunden> (0 → 30s)
+    #[arg(long = "keepalive")]
+    keepalive: Option<u32>,
+
+    /// -L [bind_addr:]port:host:hostport (mehrfach möglich)
+    #[arg(short = 'L', long = "local-forward")]
+    local_forwards: Vec<ForwardSpec>,
+
+    /// -R port:host:hostport – der Server lauscht auf `port`, Ziel ist `host:hostport` hier
+    /// (mehrfach möglich; eine Bind-Adresse wird ignoriert, das regelt `GatewayPorts`)
+    #[arg(short = 'R', long = "remote-forward")]
+    remote_forwards: Vec<ForwardSpec>,
+
+    /// -D [bind_addr:]port – SOCKS5-Proxy (mehrfach möglich)
+    #[arg(short = 'D', long = "dynamic-forward")]
+    dynamic_forwards: Vec<String>,
+
+    /// -N: keine Shell, nur Forwardings halten (bis Ctrl+C oder Verbindungsabbruch)
+    #[arg(short = 'N')]
+    no_shell: bool,
+
+    /// -scp-recv <remote>: Datei per SCP holen und auf stdout ausgeben
+    #[arg(long = "scp-recv", value_name = "REMOTE")]
+    scp_recv: Option<String>,
+
+    /// -scp-send <local> <remote>: Datei per SCP hochladen
+    #[arg(long = "scp-send", num_args = 2, value_names = ["LOCAL", "REMOTE"])]
+    scp_send: Option<Vec<String>>,
+
+    /// -m <datei>: Befehle aus der Datei per exec ausführen (ohne PTY), dann mit deren Exit-Code beenden
+    #[arg(short = 'm', value_name = "FILE")]
+    command_file: Option<String>,
+
+    /// -s: Remote-Befehl ist ein Subsystem (Default `sftp`), z. B. für WinSCP
+    #[arg(short = 's')]
+    subsystem: bool,
+
+    /// -F <datei>: ssh_config für Host-Aliase (Default ~/.ssh/config)
+    #[arg(short = 'F', value_name = "FILE")]
+    ssh_config: Option<PathBuf>,
+
+    /// -J [user@]host[:port][,...] – über Jump-Host(s) verbinden
+    #[arg(short = 'J', long = "jump")]
+    jump: Option<String>,
+
+    /// -set VAR=val: Umgebungsvariable für die Shell (mehrfach möglich)
+    #[arg(long = "set", value_name = "VAR=val", value_parser = parse_env)]
+    env: Vec<(String, String)>,
+
+    /// -term <name>: TERM für das PTY (Default xterm-256color)
+    #[arg(long = "term", default_value = starr_core::DEFAULT_TERM)]
+    term: String,
+
+    /// -4 / -6: IPv4 bzw. IPv6 zuerst probieren
+    #[arg(short = '4', conflicts_with = "ipv6")]
+    _ipv4: bool,
+
+    #[arg(short = '6')]
+    ipv6: bool,
+
+    /// -log <datei>: Session-Mitschnitt (roh, anhängend)
+    #[arg(long = "log", value_name = "FILE")]
+    log: Option<String>,
+
+    /// Log zeilenweise mit Zeitstempel statt roh
+    #[arg(long = "log-timestamps", requires = "log")]
+    log_timestamps: bool,
+
+    /// -c <liste>: erlaubte Cipher, kommagetrennt in Präferenzreihenfolge
+    #[arg(
+        short = 'c',
+        long = "ciphers",
+        value_name = "LIST",
+        value_delimiter = ','
+    )]
+    ciphers: Vec<String>,
+
+    /// -kex <liste>: erlaubte Kex-Verfahren
+    #[arg(long = "kex", value_name = "LIST", value_delimiter = ',')]
+    kex: Vec<String>,
+
+    /// -hostkey-algos <liste>: erlaubte Host-Key-Typen
+    #[arg(long = "hostkey-algos", value_name = "LIST", value_delimiter = ',')]
+    host_key_algos: Vec<String>,
+
+    /// -macs <liste>: erlaubte MACs
+    #[arg(long = "macs", value_name = "LIST", value_delimiter = ',')]
+    macs: Vec<String>,
+
+    /// -C: Kompression (zlib) aktivieren
+    #[arg(short = 'C', long = "compress")]
+    compress: bool,
+
+    /// -A: ssh-agent / Pageant verwenden
+    #[arg(short = 'A', long = "agent")]
+    agent: bool,
+
     /// akzeptiere, aber ignoriere plink-kompat Flags:
     #[arg(long = "ssh", help = "ignored (plink compat)")]
     _ssh: bool,
 
-    #[arg(long = "batch", help = "ignored (plink compat)")]
-    _batch: bool,
+    /// WinSCP schickt das immer mit; Starr fragt nach der Anmeldung ohnehin nichts mehr ab
+    #[arg(long = "no-antispoof", help = "ignored (plink compat)")]
+    _no_antispoof: bool,
+
+    /// -v: Diagnose auf stderr, mehrfach für mehr Details (-vv)
+    #[arg(short = 'v', action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// -run "cmd": nach dem Öffnen der Shell einmal ausführen (z. B. `tmux attach`)
+    #[arg(long = "run", value_name = "CMD")]
+    run: Option<String>,
+
+    /// -test: nur Handshake + Anmeldung prüfen, dann trennen (Exit-Code 0 = OK)
+    #[arg(long = "test")]
+    test: bool,
+
+    /// -batch: nie nachfragen (Passwort, OTP, Host-Key) – fehlt etwas, mit Fehler beenden
+    #[arg(long = "batch")]
+    batch: bool,
 
     // Sammel alle unbekannten/zusätzlichen Tokens (wir ignorieren die später)
     #[arg(hide = true)]
@@ -56,11 +188,17 @@ struct Args {
 }
 
 fn main() -> Result<()> {
-    let a = Args::parse();
+    let a = Args::parse_from(plink_style_args(std::env::args()));
 
     // 1) Host/User ermitteln (user@host oder getrennt)
-    let user = a.user.unwrap_or_else(whoami::username);
     let mut host_opt = a.host;
+    // `-s host sftp`: der Subsystem-Name steht hinter dem Host und landet in extras
+    let subsystem = a.subsystem.then(|| {
+        host_opt
+            .as_ref()
+            .and_then(|_| a.extras.iter().find(|s| !s.starts_with('-')).cloned())
+            .unwrap_or_else(|| "sftp".into())
+    });
 
     // WinSCP schmeißt den Host manchmal in "extras". Pick ihn da raus, falls nötig.
     if host_opt.is_none() {
@@ -68,32 +206,202 @@ fn main() -> Result<()> {
     }
 
     let host_raw = host_opt.ok_or_else(|| anyhow!("Kein Host übergeben"))?;
-    let (user_final, host) = if let Some((u, h)) = host_raw.split_once('@') {
-        (u.to_string(), h.to_string())
-    } else {
-        (user, host_raw)
-    };
+    let (user_final, host, port, alias) =
+        resolve_target(&host_raw, a.user, a.port, a.ssh_config.as_deref())?;
+    let (alias, alias_jump) = alias.map_or((None, None), |(p, jump)| (Some(p), jump));
 
-    // 2) Profil bauen
-    let prof = StarrProfile {
+    // 2) Profil bauen (erstes -i ist der Haupt-Key, weitere werden danach probiert;
+    // ohne -i die IdentityFiles des Alias)
+    let (key_path, identities) = match (a.identity.is_empty(), &alias) {
+        (true, Some(p)) => (p.key_path.clone(), p.identities.clone()),
+        _ => {
+            let mut keys = a.identity.into_iter().map(PathBuf::from);
+            (keys.next(), keys.collect())
+        }
+    };
+    let mut prof = StarrProfile {
         host,
-        port: a.port,
+        port,
         user: user_final,
-        key_path: a.identity.map(Into::into),
+        key_path,
+        identities,
         password: a.password,
         key_passphrase: a.passphrase,
+        key_data: None,
+        use_agent: a.agent,
+        known_hosts_path: None,
+        connect_timeout: a.connect_timeout.map(Duration::from_secs),
+        handshake_timeout: a.handshake_timeout.map(Duration::from_secs),
+        // Ein Tunnel ohne Shell ist meist idle → Keepalive an, damit ihn keine Firewall kappt
+        keepalive_interval: a.keepalive.or(a.no_shell.then_some(0)),
+        split_stderr: true,
+        compress: a.compress,
+        env: a.env,
+        term: a.term,
+        prefer_ipv6: a.ipv6,
+        jump: None,
+        ciphers: a.ciphers,
+        kex: a.kex,
+        host_key_algos: a.host_key_algos,
+        macs: a.macs,
+        on_connect: a.run,
+        no_shell: a.no_shell,
     };
+    connect_or_exit(&prof, a.batch, || check_key_files(&prof));
+    // Fehlendes vorher im Terminal abfragen (vor den Jump-Hops, die erben es)
+    if !a.batch && io::stdin().is_terminal() {
+        prompt_missing_secrets(&mut prof)?;
+    }
+    // -J ersetzt den ProxyJump des Alias
+    if let Some(spec) = a.jump.as_deref().or(alias_jump.as_deref()) {
+        prof.set_jump_hosts(spec)?;
+    }
 
-    // 3) Verbinden
-    let sess = match StarrSession::connect(&prof) {
-        Ok(s) => s,
-        Err(e) => {
-            eprintln!("Verbindungsfehler: {e}");
-            std::process::exit(1);
+    // 2a) -test: Zugangsdaten prüfen, ohne Shell
+    if a.test {
+        let info = connect_or_exit(&prof, a.batch, || StarrSession::test_connect(&prof));
+        if a.verbose > 0 {
+            log_connection_info(&info);
         }
+        eprintln!("Verbindung OK: {}@{}:{}", prof.user, prof.host, prof.port);
+        std::process::exit(0);
+    }
+
+    // 2b) -m: Skript per exec statt interaktiver Shell
+    if let Some(ref path) = a.command_file {
+        let script = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Befehlsdatei '{path}' nicht lesbar: {e}"))?;
+        let out = connect_or_exit(&prof, a.batch, || StarrSession::exec(&prof, &script));
+        io::stdout().write_all(&out.stdout)?;
+        io::stdout().flush()?;
+        io::stderr().write_all(&out.stderr)?;
+        std::process::exit(out.exit_code);
+    }
+
+    // 2c) -s: Subsystem (WinSCP-SFTP) – rohe Bytes stdin ↔ Channel ↔ stdout, kein PTY
+    if let Some(ref name) = subsystem {
+        let code = connect_or_exit(&prof, a.batch, || {
+            let mut prompter = StdinPrompter;
+            let prompt: Option<&mut dyn PromptResponder> =
+                if a.batch { None } else { Some(&mut prompter) };
+            StarrSession::run_subsystem(
+                &prof,
+                prompt,
+                name,
+                io::stdin(),
+                &mut io::stdout(),
+                &mut io::stderr(),
+            )
+            .map_err(StarrError::from)
+        });
+        std::process::exit(code);
+    }
+
+    // 3) Verbinden (unbekannter Host-Key → einmal nachfragen, dann erneut).
+    // Mit -batch ohne Prompter: keyboard-interactive geht nur mit -pw, sonst Fehler.
+    let batch = a.batch;
+    let events = (a.verbose > 0).then(|| spawn_event_logger(a.verbose));
+    if a.verbose > 0 {
+        eprintln!(
+            "debug1: Verbinde mit {}@{}:{}",
+            prof.user, prof.host, prof.port
+        );
+    }
+    let connect = |prof: &StarrProfile| {
+        let mut prompter = StdinPrompter;
+        let prompt: Option<&mut dyn PromptResponder> =
+            if batch { None } else { Some(&mut prompter) };
+        StarrSession::connect_with_config(prof, prompt, ReaderConfig::default(), events.clone())
     };
+    let sess = connect_or_exit(&prof, batch, || connect(&prof));
+    if a.verbose > 0 {
+        log_connection_info(&sess.connection_info());
+    }
 
-    // 4) stdin → remote
+    for r in sess.rejected_env() {
+        eprintln!("Umgebungsvariable abgelehnt (AcceptEnv?) – {r}");
+    }
+
+    if let Some(ref path) = a.log {
+        let mode = if a.log_timestamps {
+            LogMode::Timestamped
+        } else {
+            LogMode::Raw
+        };
+        sess.start_logging(Path::new(path), mode)?;
+    }
+
+    // 3a) SCP-Modus: einmal übertragen, dann raus
+    if let Some(remote) = &a.scp_recv {
+        let (data, _) = sess.scp_recv(Path::new(remote))?;
+        io::stdout().write_all(&data)?;
+        io::stdout().flush()?;
+        return Ok(());
+    }
+    if let Some([local, remote]) = a.scp_send.as_deref() {
+        sess.scp_send(Path::new(local), Path::new(remote), 0o644)?;
+        return Ok(());
+    }
+
+    // 3b) Port-Forwardings (leben bis Prozessende)
+    let mut _forwards = Vec::new();
+    for f in &a.local_forwards {
+        match sess.forward_local(&f.bind_addr, f.bind_port, &f.host, f.host_port) {
+            Ok(h) => _forwards.push(h),
+            Err(e) => {
+                eprintln!(
+                    "Forwarding {}:{} fehlgeschlagen: {e}",
+                    f.bind_addr, f.bind_port
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let mut _remote_forwards = Vec::new();
+    for f in &a.remote_forwards {
+        match sess.forward_remote(f.bind_port, &f.host, f.host_port) {
+            Ok(h) => _remote_forwards.push(h),
+            Err(e) => {
+                eprintln!("Remote-Forwarding {} fehlgeschlagen: {e}", f.bind_port);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    for d in &a.dynamic_forwards {
+        let (bind_addr, port) = match d.rsplit_once(':') {
+            Some((addr, port)) => (addr, port),
+            None => ("127.0.0.1", d.as_str()),
+        };
+        let res = port
+            .parse::<u16>()
+            .map_err(|_| anyhow!("Ungültiger Port '{port}'"))
+            .and_then(|port| sess.forward_socks(bind_addr, port));
+        match res {
+            Ok(h) => _forwards.push(h),
+            Err(e) => {
+                eprintln!("SOCKS-Proxy {d} fehlgeschlagen: {e}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // 3c) -N: nur die Forwardings am Leben halten; Ctrl+C beendet den Prozess
+    if a.no_shell {
+        if _forwards.is_empty() && _remote_forwards.is_empty() {
+            eprintln!("Hinweis: -N ohne -L/-D/-R – es gibt nichts weiterzuleiten");
+        }
+        while !sess.is_closed() {
+            thread::sleep(Duration::from_millis(250));
+        }
+        eprintln!("Verbindung getrennt");
+        std::process::exit(255);
+    }
+
+    // 4) stdin → remote, Bytes unverändert (Pfeiltasten, Ctrl+C, Nicht-UTF-8 aus Pipes)
+    // Der Thread hängt ggf. in einem blockierenden stdin-read; process::exit beendet ihn mit.
+    console::enable_raw();
     let _writer = {
         let s = sess.weak_clone();
         thread::spawn(move || {
@@ -101,9 +409,15 @@ fn main() -> Result<()> {
             let mut tmp = [0u8; 4096];
             loop {
                 match inb.read(&mut tmp) {
-                    Ok(0) => break,
+                    // stdin zu (Pipe fertig) → Remote bekommt EOF und kann sich beenden
+                    Ok(0) => {
+                        let _ = s.send_eof();
+                        break;
+                    }
                     Ok(n) => {
-                        let _ = s.send(std::str::from_utf8(&tmp[..n]).unwrap_or_default());
+                        if s.send_bytes(&tmp[..n]).is_err() {
+                            break; // Channel zu
+                        }
                     }
                     Err(_) => break,
                 }
@@ -111,15 +425,403 @@ fn main() -> Result<()> {
         })
     };
 
-    // 5) remote → stdout (einfaches Polling)
+    // 5) remote → stdout (einfaches Polling), bis der Remote den Channel schließt
+    let mut utf8 = Utf8Stream::new();
     loop {
-        let out = sess.read_string();
+        // erst Zustand merken, dann lesen → nach dem Schließen geht kein Rest verloren
+        let closed = sess.is_closed();
+
+        let out = utf8.push(&sess.read_bytes());
         if !out.is_empty() {
             print!("{out}");
+            // Prompts enden ohne Zeilenumbruch – sonst hängen sie im LineWriter
+            let _ = io::stdout().flush();
+        }
+        let err = sess.read_stderr_bytes();
+        if !err.is_empty() {
+            let _ = io::stderr().write_all(&err);
+        }
+
+        if closed {
+            print!("{}", utf8.flush());
+            let _ = io::stdout().flush();
+            console::restore();
+            // wie OpenSSH: 255, wenn kein Exit-Status kam (Verbindungsfehler)
+            std::process::exit(sess.exit_status().unwrap_or(255));
         }
         thread::sleep(Duration::from_millis(25));
     }
+}
+
+/// plink-Optionen mit einem Bindestrich (`-pw`, `-batch`, `-no-antispoof`, wie WinSCP sie
+/// übergibt) in clap-Langform umschreiben. Einbuchstabige Flags bleiben, wie sie sind.
+fn plink_style_args(args: impl Iterator<Item = String>) -> Vec<String> {
+    const LONG: &[&str] = &[
+        "ssh",
+        "batch",
+        "test",
+        "run",
+        "no-antispoof",
+        "pw",
+        "pass",
+        "connect-timeout",
+        "handshake-timeout",
+        "keepalive",
+        "scp-recv",
+        "scp-send",
+        "set",
+        "term",
+        "log",
+        "log-timestamps",
+        "ciphers",
+        "kex",
+        "hostkey-algos",
+        "macs",
+    ];
+    args.map(|arg| match arg.strip_prefix('-') {
+        Some(name) if LONG.contains(&name) => format!("-{arg}"),
+        _ => arg,
+    })
+    .collect()
+}
+
+/// `[user@]host[:port]` + `-l`/`-P` → (user, host, port, Alias und ProxyJump aus der ssh_config).
+/// Vorrang: Flags, dann `user@`/`:port` im Argument, dann der Alias, zuletzt die Defaults.
+fn resolve_target(
+    host_raw: &str,
+    user: Option<String>,
+    port: Option<u16>,
+    ssh_config: Option<&Path>,
+) -> Result<(String, String, u16, Option<SshHost>)> {
+    let (embedded_user, host) = match host_raw.split_once('@') {
+        Some((u, h)) => (Some(u.to_string()), h),
+        None => (None, host_raw),
+    };
+    // host, host:port, ::1, [::1]:port (Port 0 = keiner angegeben)
+    let (host, embedded_port) = split_host_port(host, 0).map_err(|e| anyhow!(e))?;
+    let alias = ssh_config_host(&host, ssh_config)?;
+    let alias_user = alias
+        .as_ref()
+        .map(|(p, _)| p.user.clone())
+        .filter(|u| !u.is_empty());
+    let user = user
+        .or(embedded_user)
+        .or(alias_user)
+        .unwrap_or_else(whoami::username);
+    let port = port
+        .or((embedded_port != 0).then_some(embedded_port))
+        .or(alias.as_ref().map(|(p, _)| p.port));
+    let host = alias.as_ref().map_or(host, |(p, _)| p.host.clone());
+    Ok((user, host, port.unwrap_or(22), alias))
+}
+
+/// Profil eines ssh_config-Alias und seine ProxyJump-Angabe (erst anwenden, wenn das
+/// Profil fertig ist – die Hops erben User und Zugangsdaten).
+type SshHost = (StarrProfile, Option<String>);
+
+/// Host-Alias aus der ssh_config (`-F`, sonst ~/.ssh/config) wie bei `ssh alias`.
+/// IP-Adressen werden nie nachgeschlagen; fehlt die Standard-Datei, gibt es eben keinen Alias.
+fn ssh_config_host(host: &str, file: Option<&Path>) -> Result<Option<SshHost>> {
+    if host.parse::<IpAddr>().is_ok() {
+        return Ok(None);
+    }
+    let text = match file {
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("{} nicht lesbar: {e}", path.display()))?,
+        None => match default_ssh_config_path().and_then(|p| std::fs::read_to_string(p).ok()) {
+            Some(text) => text,
+            None => return Ok(None),
+        },
+    };
+    Ok(resolve_ssh_host(&text, host))
+}
+
+/// -v: Session-Events aus dem Core als `debugN:`-Zeilen auf stderr (stdout bleibt sauber).
+fn spawn_event_logger(verbosity: u8) -> mpsc::Sender<SessionEvent> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for ev in rx {
+            let (level, msg) = describe_event(&ev);
+            if level <= verbosity {
+                eprintln!("debug{level}: {msg}");
+            }
+        }
+    });
+    tx
+}
+
+/// Stufe + Text je Event; gelesene Bytes erst ab -vvv.
+fn describe_event(ev: &SessionEvent) -> (u8, String) {
+    match ev {
+        SessionEvent::Connecting => (2, "Baue TCP-Verbindung auf".into()),
+        SessionEvent::Connected(addr) => (1, format!("TCP verbunden mit {addr}")),
+        SessionEvent::Handshaked => (1, "SSH-Handshake abgeschlossen, Host-Key geprüft".into()),
+        SessionEvent::Negotiated {
+            kex,
+            host_key,
+            cipher,
+            mac,
+        } => (
+            1,
+            format!("kex: {kex}, Host-Key: {host_key}, Cipher: {cipher}, MAC: {mac}"),
+        ),
+        SessionEvent::Authenticating(m) => (1, format!("Versuche Authentifizierung: {m}")),
+        SessionEvent::AuthFailed { method, error } => {
+            (1, format!("{method} fehlgeschlagen: {error}"))
+        }
+        SessionEvent::IdentityAccepted(key) => (1, format!("Key angenommen: {}", key.display())),
+        SessionEvent::PasswordExpired => (
+            1,
+            "Passwort abgelaufen, Änderung über keyboard-interactive".into(),
+        ),
+        // steht schon ohne -v auf stderr (check_key_files)
+        SessionEvent::Warning(w) => (2, format!("Warnung: {w}")),
+        SessionEvent::Authenticated => (1, "Authentifizierung erfolgreich".into()),
+        SessionEvent::ChannelOpened => (1, "Session-Channel geöffnet".into()),
+        SessionEvent::ShellOpened => (1, "PTY + Shell gestartet".into()),
+        SessionEvent::Eof => (1, "Channel geschlossen".into()),
+        SessionEvent::Error(e) => (1, format!("Fehler: {e}")),
+        SessionEvent::BytesRead(n) => (3, format!("{n} Bytes empfangen")),
+    }
+}
 
-    // (nie erreicht; Ctrl+C beendet)
-    // Ok(())
+/// -v: Banner und Verfahren je Richtung nach dem Verbinden (hilft, wenn ein Server
+/// Algorithmen ablehnt).
+fn log_connection_info(info: &ConnectionInfo) {
+    if let Some(b) = &info.banner {
+        eprintln!("debug1: Server-Version: {b}");
+    }
+    eprintln!("debug1: kex: {}, Host-Key: {}", info.kex, info.host_key);
+    eprintln!(
+        "debug1: Client → Server: {} / {}",
+        info.cipher_cs, info.mac_cs
+    );
+    eprintln!(
+        "debug1: Server → Client: {} / {}",
+        info.cipher_sc, info.mac_sc
+    );
+}
+
+/// Baut die Verbindung über `f` auf. Unbekannter Host-Key → nachfragen (mit -batch nie),
+/// speichern und einmal neu versuchen; jeder andere Fehler beendet plink mit Exit-Code 1.
+fn connect_or_exit<T>(
+    prof: &StarrProfile,
+    batch: bool,
+    mut f: impl FnMut() -> Result<T, StarrError>,
+) -> T {
+    let e = match f() {
+        Ok(v) => return v,
+        Err(e) => e,
+    };
+    match e {
+        StarrError::HostKey(err @ ConnectError::UnknownHostKey { .. }) if batch => {
+            eprintln!("{err}");
+            eprintln!(
+                "-batch: unbekannter Host-Key wird nicht übernommen, Verbindung abgebrochen."
+            );
+            std::process::exit(1);
+        }
+        StarrError::HostKey(err @ ConnectError::UnknownHostKey { .. }) => {
+            if !ask_trust_host_key(&err) {
+                eprintln!("Abgebrochen.");
+                std::process::exit(1);
+            }
+            if let Err(e) = trust_host_key(prof, &err) {
+                eprintln!("known_hosts: {e:#}");
+                std::process::exit(1);
+            }
+            f().unwrap_or_else(|e| {
+                eprintln!("Verbindungsfehler: {e}");
+                std::process::exit(1);
+            })
+        }
+        StarrError::HostKey(err) => {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+        e @ StarrError::Auth { .. } => {
+            eprintln!("Anmeldung fehlgeschlagen: {e}");
+            std::process::exit(1);
+        }
+        e => {
+            eprintln!("Verbindungsfehler: {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// `VAR=val` für `-set`.
+fn parse_env(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((k, v)) if !k.is_empty() => Ok((k.to_string(), v.to_string())),
+        _ => Err(format!("erwartet VAR=val, nicht '{s}'")),
+    }
+}
+
+/// TOFU-Abfrage auf stderr/stdin, wie plink/OpenSSH.
+fn ask_trust_host_key(err: &ConnectError) -> bool {
+    if let ConnectError::UnknownHostKey {
+        host, fingerprint, ..
+    } = err
+    {
+        eprintln!("Der Host-Key von {host} ist nicht bekannt.");
+        eprintln!("Fingerprint: {fingerprint}");
+    }
+    eprint!("Host-Key vertrauen und in known_hosts speichern? (y/n) ");
+    let _ = io::stderr().flush();
+
+    let mut line = String::new();
+    if io::stdin().lock().read_line(&mut line).is_err() {
+        return false;
+    }
+    matches!(line.trim(), "y" | "Y" | "yes" | "j" | "ja")
+}
+
+/// Key-Dateien vor dem Verbinden prüfen: zu offene Rechte nur als Warnung; ist ohne Agent
+/// keiner der Keys lesbar, gleich abbrechen statt erst nach dem Handshake.
+fn check_key_files(prof: &StarrProfile) -> Result<(), StarrError> {
+    let keys: Vec<&PathBuf> = prof.key_path.iter().chain(&prof.identities).collect();
+    for w in keys.iter().filter_map(|k| key_file_warning(k)) {
+        eprintln!("Warnung: {w}");
+    }
+    if prof.use_agent {
+        return Ok(());
+    }
+    let mut first_err = None;
+    for key in keys {
+        match check_key_file(key) {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                first_err.get_or_insert(e);
+            }
+        }
+    }
+    first_err.map_or(Ok(()), Err)
+}
+
+/// Wie plink/OpenSSH ohne `-pw`/`-pass`: Passphrase für einen verschlüsselten Key und,
+/// wenn es sonst nichts zum Anmelden gibt, das Passwort – beides ohne Echo.
+fn prompt_missing_secrets(prof: &mut StarrProfile) -> Result<()> {
+    if prof.key_passphrase.is_none() {
+        let mut keys = prof.key_path.iter().chain(&prof.identities);
+        if let Some(key) = keys.find(|k| key_file_is_encrypted(k).unwrap_or(false)) {
+            prof.key_passphrase = Some(console::read_hidden(&format!(
+                "Passphrase für {}: ",
+                key.display()
+            ))?);
+        }
+    }
+    let other_auth = prof.use_agent || prof.key_path.is_some() || !prof.identities.is_empty();
+    if prof.password.is_none() && !other_auth {
+        prof.password = Some(console::read_hidden(&format!(
+            "Passwort für {}@{}: ",
+            prof.user, prof.host
+        ))?);
+    }
+    Ok(())
+}
+
+/// keyboard-interactive über stderr/stdin (2FA/OTP); verdeckte Prompts ohne Echo.
+struct StdinPrompter;
+
+impl PromptResponder for StdinPrompter {
+    fn respond(
+        &mut self,
+        _username: &str,
+        instructions: &str,
+        prompts: &[AuthPrompt],
+    ) -> Vec<String> {
+        if !instructions.is_empty() {
+            eprintln!("{instructions}");
+        }
+        prompts
+            .iter()
+            .map(|p| {
+                if !p.echo {
+                    return console::read_hidden(&p.text).unwrap_or_default();
+                }
+                eprint!("{}", p.text);
+                let _ = io::stderr().flush();
+                let mut line = String::new();
+                let _ = io::stdin().lock().read_line(&mut line);
+                line.trim_end_matches(['\r', '\n']).to_string()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_target_matrix() {
+        let me = whoami::username();
+        let config = std::env::temp_dir().join(format!("starr-plink-test-{}", std::process::id()));
+        std::fs::write(
+            &config,
+            "Host web\n  HostName 10.0.0.5\n  User deploy\n  Port 2222\n\
+             Host app\n  User deploy\n  ProxyJump bastion:2022\n",
+        )
+        .unwrap();
+        let rows = [
+            ("host", None, None, (me.as_str(), "host", 22), ""),
+            ("alice@host", None, None, ("alice", "host", 22), ""),
+            ("host", Some("bob"), None, ("bob", "host", 22), ""),
+            ("alice@host", Some("bob"), None, ("bob", "host", 22), ""),
+            ("host:2222", None, None, (me.as_str(), "host", 2222), ""),
+            (
+                "host:2222",
+                None,
+                Some(2200),
+                (me.as_str(), "host", 2200),
+                "",
+            ),
+            (
+                "alice@[::1]:22",
+                Some("bob"),
+                Some(2200),
+                ("bob", "::1", 2200),
+                "",
+            ),
+            ("web", None, None, ("deploy", "10.0.0.5", 2222), ""),
+            ("web:22", Some("bob"), None, ("bob", "10.0.0.5", 22), ""),
+            ("10.0.0.5", None, None, (me.as_str(), "10.0.0.5", 22), ""),
+            // Hop ohne `user@` erbt den fertigen User, auch aus -l
+            (
+                "app",
+                None,
+                None,
+                ("deploy", "app", 22),
+                "deploy@bastion:2022",
+            ),
+            (
+                "app",
+                Some("bob"),
+                None,
+                ("bob", "app", 22),
+                "bob@bastion:2022",
+            ),
+        ];
+        for (arg, user, port, (want_user, want_host, want_port), want_jump) in rows {
+            let (u, h, p, alias) =
+                resolve_target(arg, user.map(String::from), port, Some(&config)).unwrap();
+            assert_eq!(
+                (u.as_str(), h.as_str(), p),
+                (want_user, want_host, want_port),
+                "{arg} -l {user:?} -P {port:?}"
+            );
+            let mut prof = StarrProfile {
+                host: h,
+                port: p,
+                user: u,
+                ..Default::default()
+            };
+            if let Some(spec) = alias.and_then(|(_, jump)| jump) {
+                prof.set_jump_hosts(&spec).unwrap();
+            }
+            assert_eq!(prof.jump_hosts_spec(), want_jump, "{arg} -l {user:?}");
+        }
+        std::fs::remove_file(&config).unwrap();
+    }
 }